@@ -3,26 +3,131 @@ use egui::{Context, Frame, Stroke, Margin, RichText, Color32};
 
 use crate::ui;
 use crate::ui::theme::{apply as apply_theme, palette, ThemeKind};
-use crate::game::{Game, GameInput, BOARD_W, BOARD_H};
+use crate::game::{handling::Handling, piece::PieceSet, random::{Randomizer, RandomizerKind}, rng::PRNG_VERSION, rules::RuleSet, timestep::{FixedTimestep, TICK_DT}, Game, GameInput, MAX_START_LEVEL};
+use crate::settings::{GravityMode, KeyBindings, Settings};
 
 pub struct App {
     theme: ThemeKind,
     game: Game,
     last: Instant,
+    timestep: FixedTimestep,
+    latched: GameInput, // input seen since the last tick ran
     ghost_enabled: bool,
+    peek_enabled: bool,
     gravity_pulse_mode: bool,
+    cascade_mode: bool,
+    combo_chain_mode: bool,
+    handling: Handling,
+    keybindings: KeyBindings,
+    rulesets: Vec<RuleSet>, // built-in presets, then the user's
+    ruleset: usize, // index into rulesets
+    start_level: u32,
+    piece_sets: Vec<PieceSet>, // standard tetrominoes, then the user's
+    piece_set: usize, // index into piece_sets
+    randomizer: RandomizerKind,
+    fixed_sequence: String,
+    keep_seed: bool,
+    board_size: (i16, i16),
+    seed_input: String,
+    results_dismissed: bool,
+    saved: Settings, // last settings written to disk
 }
 
 impl App {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let theme = ThemeKind::Dark;
-        apply_theme(theme, &cc.egui_ctx);
-        Self { 
-            theme, 
-            game: Game::new(), 
+        let settings = Settings::load();
+        apply_theme(settings.theme, &cc.egui_ctx);
+        let rulesets = Settings::rulesets();
+        let ruleset = rulesets.iter().position(|r| r.name == settings.ruleset).unwrap_or(0);
+        let piece_sets = Settings::piece_sets();
+        let piece_set = piece_sets.iter().position(|s| s.name == settings.piece_set).unwrap_or(0);
+        let mut app = Self { 
+            theme: settings.theme, 
+            game: Game::new(settings.randomizer.build(None, &[], piece_sets[piece_set].len())),
             last: Instant::now(),
-            ghost_enabled: true,
-            gravity_pulse_mode: false,
+            timestep: FixedTimestep::default(),
+            latched: GameInput::default(),
+            ghost_enabled: settings.ghost_enabled,
+            peek_enabled: settings.peek_enabled,
+            gravity_pulse_mode: settings.gravity_mode == GravityMode::Pulse,
+            cascade_mode: settings.cascade,
+            combo_chain_mode: settings.combo_chain,
+            handling: settings.handling,
+            keybindings: settings.keybindings.clone(),
+            rulesets,
+            ruleset,
+            start_level: settings.start_level.min(MAX_START_LEVEL),
+            piece_sets,
+            piece_set,
+            randomizer: settings.randomizer,
+            fixed_sequence: settings.fixed_sequence.clone(),
+            keep_seed: settings.keep_seed,
+            board_size: (settings.board_width, settings.board_height),
+            seed_input: String::new(),
+            results_dismissed: false,
+            saved: settings,
+        };
+        app.restart();
+        app
+    }
+
+    fn new_randomizer(&self) -> Box<dyn Randomizer> {
+        let set = &self.piece_sets[self.piece_set];
+        self.randomizer.build(None, &set.parse_sequence(&self.fixed_sequence), set.len())
+    }
+
+    /// New game, on the current seed if the player asked to keep it.
+    fn restart(&mut self) {
+        let seed = self.keep_seed.then(|| self.game.seed());
+        self.start(seed);
+    }
+
+    fn start(&mut self, seed: Option<u64>) {
+        self.game = match seed {
+            Some(seed) => Game::with_seed(self.new_randomizer(), seed),
+            None => Game::new(self.new_randomizer()),
+        }
+        .with_board_size(self.board_size.0, self.board_size.1)
+        .with_rules(self.rulesets[self.ruleset].clone())
+        .with_pieces(self.piece_sets[self.piece_set].clone())
+        .with_start_level(self.start_level);
+        // Apply current settings to new game
+        self.game.set_pulse_gravity(self.gravity_pulse_mode);
+        self.game.set_cascade(self.cascade_mode);
+        self.game.set_combo_chain(self.combo_chain_mode);
+        self.game.handling = self.handling;
+        self.results_dismissed = false;
+    }
+
+    fn settings(&self) -> Settings {
+        Settings {
+            theme: self.theme,
+            ghost_enabled: self.ghost_enabled,
+            peek_enabled: self.peek_enabled,
+            gravity_mode: if self.gravity_pulse_mode { GravityMode::Pulse } else { GravityMode::Normal },
+            cascade: self.cascade_mode,
+            combo_chain: self.combo_chain_mode,
+            handling: self.handling,
+            keybindings: self.keybindings.clone(),
+            ruleset: self.rulesets[self.ruleset].name.clone(),
+            start_level: self.start_level,
+            piece_set: self.piece_sets[self.piece_set].name.clone(),
+            randomizer: self.randomizer,
+            fixed_sequence: self.fixed_sequence.clone(),
+            keep_seed: self.keep_seed,
+            board_width: self.board_size.0,
+            board_height: self.board_size.1,
+        }
+    }
+
+    /// Write settings to disk whenever something changed since the last save.
+    fn persist_settings(&mut self) {
+        let current = self.settings();
+        if current != self.saved {
+            if let Err(err) = current.save() {
+                eprintln!("Failed to save settings: {:#}", err);
+            }
+            self.saved = current;
         }
     }
 }
@@ -34,18 +139,44 @@ impl eframe::App for App {
         let dt = (now - self.last).as_secs_f32();
         self.last = now;
 
-        let input = ctx.input(|i| GameInput {
-            left: i.key_pressed(egui::Key::ArrowLeft),     // Changed to key_pressed for discrete movement
-            right: i.key_pressed(egui::Key::ArrowRight),   // Changed to key_pressed for discrete movement
-            soft_drop: i.key_down(egui::Key::ArrowDown),   // Keep key_down for continuous soft drop
-            hard_drop: i.key_pressed(egui::Key::Space),
-            rot_cw: i.key_pressed(egui::Key::X) || i.key_pressed(egui::Key::ArrowUp),
-            rot_ccw: i.key_pressed(egui::Key::Z),
-            pause: i.key_pressed(egui::Key::P),
-            restart: i.key_pressed(egui::Key::R),
-        });
+        // Held-state snapshot; the game does its own edge detection and DAS/ARR.
+        // key_pressed also catches taps that start and end within one frame.
+        // Unknown key names in the bindings leave that action unbound.
+        // Typing into a text field (e.g. the fixed sequence) must not play the game.
+        let keys = &self.keybindings;
+        let input = if ctx.wants_keyboard_input() {
+            GameInput::default()
+        } else {
+            ctx.input(|i| {
+                let held = |key| i.key_down(key) || i.key_pressed(key);
+                let bound = |name: &str| egui::Key::from_name(name).is_some_and(held);
+                GameInput {
+                    left: bound(&keys.move_left),
+                    right: bound(&keys.move_right),
+                    soft_drop: bound(&keys.soft_drop),
+                    hard_drop: bound(&keys.hard_drop),
+                    rot_cw: bound(&keys.rotate_cw) || bound(&keys.rotate_cw_alt),
+                    rot_ccw: bound(&keys.rotate_ccw),
+                    rot_180: bound(&keys.rotate_180),
+                    hold: bound(&keys.hold),
+                    pause: bound(&keys.pause),
+                    restart: bound(&keys.restart),
+                }
+            })
+        };
 
-        self.game.update(dt, input);
+        self.game.handling = self.handling;
+        self.game.reuse_seed = self.keep_seed;
+        // Fixed-step simulation: the game only ever sees TICK_DT, however fast we render.
+        // Taps between ticks are latched so they still reach the next one.
+        self.latched = self.latched.merge(input);
+        for _ in 0..self.timestep.advance(dt) {
+            self.game.update(TICK_DT, self.latched);
+            self.latched = input;
+        }
+        if !self.game.is_over() {
+            self.results_dismissed = false;
+        }
         ctx.request_repaint();
         
         let pal = palette(self.theme);
@@ -60,62 +191,157 @@ impl eframe::App for App {
                 .inner_margin(Margin::same(16.0))
             )
             .show(ctx, |ui| {
-                let old_gravity_pulse_mode = self.gravity_pulse_mode;
-                let actions = ui::panel::right_panel(ui, &mut self.theme, self.game.paused, &mut self.ghost_enabled, &mut self.gravity_pulse_mode);
+                // Scrollable so the sidebar stays usable on short windows
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let old_gravity_pulse_mode = self.gravity_pulse_mode;
+                    let actions = ui::panel::right_panel(ui, &mut self.theme, self.game.paused, &mut self.ghost_enabled, &mut self.peek_enabled, &mut self.gravity_pulse_mode, &mut self.cascade_mode, &mut self.combo_chain_mode, &mut self.handling, &self.rulesets, &mut self.ruleset, &mut self.start_level, &self.piece_sets, &mut self.piece_set, &mut self.randomizer, &mut self.fixed_sequence, &mut self.board_size);
                 
-                // Update gravity pulse mode if it changed
-                if old_gravity_pulse_mode != self.gravity_pulse_mode {
-                    self.game.set_pulse_gravity(self.gravity_pulse_mode);
-                }
+                    // Update gravity pulse mode if it changed
+                    if old_gravity_pulse_mode != self.gravity_pulse_mode {
+                        self.game.set_pulse_gravity(self.gravity_pulse_mode);
+                    }
                 
-                if actions.pause_toggled {
-                    self.game.paused = !self.game.paused;
-                }
-                if actions.restart {
-                    self.game = Game::new();
-                    self.game.set_pulse_gravity(self.gravity_pulse_mode); // Apply current settings to new game
-                }
+                    if actions.cascade_toggled {
+                        self.game.set_cascade(self.cascade_mode);
+                    }
 
-                ui.add_space(10.0);
-                
-                // Next pieces preview
-                stats_section(ui, &pal, "⏭ NEXT", |ui| {
-                    let preview_pieces = self.game.preview_pieces(3);
-                    for (i, piece) in preview_pieces.iter().enumerate() {
-                        ui.horizontal(|ui| {
-                            ui.label(
-                                RichText::new(format!("{}:", i + 1))
-                                    .size(10.0)
-                                    .color(pal.text.gamma_multiply(0.7))
-                            );
-                            ui::draw::preview_piece(ui, &pal, *piece, 12.0);
+                    if actions.combo_chain_toggled {
+                        self.game.set_combo_chain(self.combo_chain_mode);
+                    }
+                    
+                    if actions.ruleset_changed {
+                        // A rule set brings its own randomizer; it can still be swapped afterwards
+                        self.randomizer = self.rulesets[self.ruleset].randomizer;
+                    }
+
+                    if actions.randomizer_changed || actions.ruleset_changed || actions.start_level_changed || actions.piece_set_changed || actions.board_resized {
+                        self.restart();
+                    }
+
+                    if actions.pause_toggled {
+                        self.game.paused = !self.game.paused;
+                    }
+                    if actions.restart {
+                        self.restart();
+                    }
+
+                    ui.add_space(10.0);
+
+                    // Hold slot (greyed out until the next piece locks); absent when the rules have no hold
+                    if self.game.rules.hold {
+                        stats_section(ui, &pal, "📥 HOLD", |ui| {
+                            match self.game.held() {
+                                Some(piece) => {
+                                    ui::draw::preview_piece(ui, &pal, self.game.pieces(), piece, 12.0, !self.game.hold_available());
+                                }
+                                None => {
+                                    ui.label(
+                                        RichText::new("empty")
+                                            .size(10.0)
+                                            .color(pal.text.gamma_multiply(0.5))
+                                            .italics()
+                                    );
+                                }
+                            }
                         });
-                        if i < 2 {
-                            ui.add_space(2.0);
-                        }
+
+                        ui.add_space(8.0);
                     }
-                });
                 
-                ui.add_space(8.0);
+                    // Next pieces preview
+                    stats_section(ui, &pal, "⏭ NEXT", |ui| {
+                        let preview_pieces = self.game.preview_pieces(self.game.rules.preview);
+                        for (i, piece) in preview_pieces.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(format!("{}:", i + 1))
+                                        .size(10.0)
+                                        .color(pal.text.gamma_multiply(0.7))
+                                );
+                                ui::draw::preview_piece(ui, &pal, self.game.pieces(), *piece, 12.0, false);
+                            });
+                            if i + 1 < preview_pieces.len() {
+                                ui.add_space(2.0);
+                            }
+                        }
+                    });
                 
-                // Enhanced game statistics section
-                stats_section(ui, &pal, "📊 STATS", |ui| {
-                    stat_row(ui, &pal, "Score", &format!("{:0>6}", self.game.score), pal.neon_magenta);
-                    stat_row(ui, &pal, "Level", &format!("{}", self.game.level + 1), pal.neon_cyan);
-                    stat_row(ui, &pal, "Lines", &format!("{}", self.game.lines), pal.text);
-                    stat_row(ui, &pal, "Combo", "×0", Color32::from_rgb(255, 200, 100));
-                });
+                    ui.add_space(8.0);
                 
-                ui.add_space(8.0);
+                    // Enhanced game statistics section
+                    stats_section(ui, &pal, "📊 STATS", |ui| {
+                        stat_row(ui, &pal, "Score", &format!("{:0>6}", self.game.score), pal.neon_magenta);
+                        stat_row(ui, &pal, "Level", &format!("{}", self.game.shown_level()), pal.neon_cyan);
+                        stat_row(ui, &pal, "Lines", &format!("{}", self.game.lines), pal.text);
+                        stat_row(ui, &pal, "Combo", &format!("×{}", self.game.combo()), Color32::from_rgb(255, 200, 100));
+                        stat_row(ui, &pal, "B2B", &format!("×{}", self.game.back_to_back()), pal.neon_cyan);
+                        if self.game.slow_time() > 0.0 {
+                            stat_row(ui, &pal, "Slow Time", &format!("{:.1}s", self.game.slow_time()), Color32::from_rgb(120, 170, 255));
+                        }
+                    });
                 
-                // Controls help section
-                stats_section(ui, &pal, "🎮 CONTROLS", |ui| {
-                    ui.label(RichText::new("← → : Move").color(pal.text.gamma_multiply(0.8)).size(9.0));
-                    ui.label(RichText::new("↓ : Soft Drop").color(pal.text.gamma_multiply(0.8)).size(9.0));
-                    ui.label(RichText::new("Space : Hard Drop").color(pal.text.gamma_multiply(0.8)).size(9.0));
-                    ui.label(RichText::new("Z/X : Rotate").color(pal.text.gamma_multiply(0.8)).size(9.0));
-                    ui.label(RichText::new("P : Pause").color(pal.text.gamma_multiply(0.8)).size(9.0));
-                    ui.label(RichText::new("R : Restart").color(pal.text.gamma_multiply(0.8)).size(9.0));
+                    ui.add_space(8.0);
+
+                    // Timed combo chain (optional mechanic)
+                    if let Some(chain) = self.game.combo_chain() {
+                        stats_section(ui, &pal, "⚡ CHAIN", |ui| {
+                            ui::draw::combo_chain(ui, &pal, chain.multiplier(), chain.remaining());
+                        });
+                        ui.add_space(8.0);
+                    }
+                
+                    // Seed: shown for sharing, or typed in to replay a run
+                    let seed = self.game.seed();
+                    let start_seed = stats_section(ui, &pal, "🌱 SEED", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(seed.to_string()).monospace().color(pal.text));
+                            // Seeds only replay the same pieces under the same generator version
+                            ui.label(RichText::new(format!("v{}", PRNG_VERSION)).size(9.0).color(pal.text.gamma_multiply(0.6)))
+                                .on_hover_text("Piece generator version");
+                            if ui.small_button("📋").on_hover_text("Copy seed").clicked() {
+                                ui.ctx().copy_text(seed.to_string());
+                            }
+                        });
+                        let start = ui.horizontal(|ui| {
+                            let edit = ui.add(
+                                egui::TextEdit::singleline(&mut self.seed_input)
+                                    .hint_text("Enter a seed")
+                                    .desired_width(130.0)
+                            );
+                            let entered = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            ui.button(RichText::new("▶ START").size(11.0)).clicked() || entered
+                        }).inner;
+                        let parsed = self.seed_input.trim().parse::<u64>();
+                        if parsed.is_err() && !self.seed_input.trim().is_empty() {
+                            ui.label(RichText::new("Seed must be a whole number").color(Color32::from_rgb(255, 100, 100)).size(9.0));
+                        }
+                        ui.checkbox(&mut self.keep_seed, RichText::new("Same seed on restart").color(pal.text));
+                        if start { parsed.ok() } else { None }
+                    });
+                    if let Some(seed) = start_seed {
+                        self.start(Some(seed));
+                    }
+                    ui.add_space(8.0);
+
+                    // Controls help section
+                    let keys = &self.keybindings;
+                    let cw = [keys.rotate_cw.as_str(), keys.rotate_cw_alt.as_str()];
+                    let cw = cw.iter().filter(|k| !k.is_empty()).copied().collect::<Vec<_>>().join(" ");
+                    stats_section(ui, &pal, "🎮 CONTROLS", |ui| {
+                        let help = [
+                            format!("{} {} : Move", keys.move_left, keys.move_right),
+                            format!("{} : Soft Drop", keys.soft_drop),
+                            format!("{} : Hard Drop", keys.hard_drop),
+                            format!("{}/{} : Rotate", keys.rotate_ccw, cw),
+                            format!("{} : Rotate 180°", keys.rotate_180),
+                            format!("{} : Hold", keys.hold),
+                            format!("{} : Pause", keys.pause),
+                            format!("{} : Restart", keys.restart),
+                        ];
+                        for line in help {
+                            ui.label(RichText::new(line).color(pal.text.gamma_multiply(0.8)).size(9.0));
+                        }
+                    });
                 });
             });
 
@@ -126,8 +352,9 @@ impl eframe::App for App {
                 .inner_margin(Margin::same(20.0))
             )
             .show(ctx, |ui| {
-                let ghost = if self.ghost_enabled {
-                    Some(self.game.ghost_position())
+                let ghost_enabled = self.ghost_enabled && self.game.rules.ghost;
+                let ghost = if ghost_enabled {
+                    self.game.ghost_position()
                 } else {
                     None
                 };
@@ -135,19 +362,36 @@ impl eframe::App for App {
                 ui::draw::playfield(
                     ui, 
                     &pal, 
-                    (BOARD_W as usize, BOARD_H as usize), 
+                    self.game.pieces(),
                     self.game.board(), 
-                    Some(self.game.active()),
+                    self.game.active(),
                     ghost.as_ref(),
-                    self.ghost_enabled
+                    ghost_enabled,
+                    self.peek_enabled
                 );
             });
 
-        // Apply theme changes
-        let should_be_dark = matches!(self.theme, ThemeKind::Dark);
-        if ctx.style().visuals.dark_mode != should_be_dark {
+        if let Some(callout) = self.game.callout() {
+            ui::overlay::callout(ctx, &pal, callout, -140.0);
+        }
+
+        // Results overlay, centered on the playfield (sidebar is 280px wide)
+        if self.game.is_over() && !self.results_dismissed {
+            let actions = ui::overlay::game_over(ctx, &pal, &self.game, -140.0);
+            if actions.restart {
+                self.restart();
+            }
+            if actions.dismiss {
+                self.results_dismissed = true;
+            }
+        }
+
+        // Apply theme changes (every theme has its own panel fill)
+        if ctx.style().visuals.panel_fill != pal.bg0 {
             apply_theme(self.theme, ctx);
         }
+
+        self.persist_settings();
     }
 }
 
@@ -1,38 +1,103 @@
-use super::piece::{ActivePiece, Tetromino, blocks};
-use super::{BOARD_W, BOARD_H};
+use super::piece::{ActivePiece, Piece};
+use serde::{Deserialize, Serialize};
+
+use super::rng::Pcg32;
+use super::{BOARD_W, BOARD_H, BOARD_HIDDEN_H, MIN_BOARD_W, MAX_BOARD_W, MIN_BOARD_H, MAX_BOARD_H};
+
+/// Special effect carried by a power mino; it fires when its row is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Power {
+    /// Clears every cell within radius 1 of the power cell.
+    Nova,
+    /// Halves gravity for a few seconds.
+    SlowTime,
+}
+
+/// What left a cell filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    /// A mino of a locked piece.
+    Piece(Piece),
+    /// Part of a garbage row pushed up from below.
+    Garbage,
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Cell {
-    pub t: Tetromino,
-    pub _power: bool, // reserved for later mechanics
+    pub kind: CellKind,
+    pub power: Option<Power>,
+}
+
+/// Where the holes go in a batch of garbage rows.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GarbageHoles {
+    /// Every row keeps the hole in the same column, leaving one well to dig out.
+    #[default]
+    Clean,
+    /// Each row after the first moves the hole to another random column with this chance.
+    Messy(f64),
 }
 
+/// Outcome of `Board::push_garbage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GarbageRise {
+    /// Filled cells were pushed off the top of the vanish zone; the caller should top out.
+    pub overflow: bool,
+    /// The active piece could not be lifted clear of the stack without leaving the board.
+    pub piece_blocked: bool,
+}
+
+/// Outcome of `Board::clear_full_lines`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineClear {
+    pub lines: u32,
+    /// Powers held by cells in the cleared rows, in board order (top to bottom).
+    pub powers: Vec<Power>,
+}
+
+/// Playfield matrix. Rows `0..h` are visible; the vanish zone above them uses
+/// negative rows `-hidden..0`, so pieces can spawn and rotate above the skyline.
 #[derive(Debug, Clone)]
 pub struct Board {
     pub w: i16,
     pub h: i16,
+    pub hidden: i16,
     cells: Vec<Option<Cell>>,
 }
 
 impl Board {
     pub fn new() -> Self {
-        let w = BOARD_W;
-        let h = BOARD_H;
+        Self::with_size(BOARD_W, BOARD_H)
+    }
+
+    /// Empty board of `w` x `h` visible cells, clamped to the supported range.
+    pub fn with_size(w: i16, h: i16) -> Self {
+        let w = w.clamp(MIN_BOARD_W, MAX_BOARD_W);
+        let h = h.clamp(MIN_BOARD_H, MAX_BOARD_H);
+        let hidden = BOARD_HIDDEN_H;
         Self {
             w,
             h,
-            cells: vec![None; (w * h) as usize],
+            hidden,
+            cells: vec![None; (w * (h + hidden)) as usize],
         }
     }
 
     #[inline]
     fn idx(&self, x: i16, y: i16) -> usize {
-        (y as usize) * (self.w as usize) + (x as usize)
+        ((y + self.hidden) as usize) * (self.w as usize) + (x as usize)
     }
 
     #[inline]
     pub fn in_bounds(&self, x: i16, y: i16) -> bool {
-        x >= 0 && x < self.w && y >= 0 && y < self.h
+        x >= 0 && x < self.w && y >= -self.hidden && y < self.h
+    }
+
+    /// Topmost row index, inside the vanish zone.
+    #[inline]
+    pub fn top(&self) -> i16 {
+        -self.hidden
     }
 
     pub fn get(&self, x: i16, y: i16) -> Option<Cell> {
@@ -49,14 +114,13 @@ impl Board {
         }
     }
 
-    pub fn clear(&mut self) {
-        self.cells.fill(None);
+    /// True when no cell is occupied, vanish zone included (a perfect clear).
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Option::is_none)
     }
 
     pub fn collides(&self, p: &ActivePiece) -> bool {
-        for (ox, oy) in blocks(p.t, p.rot) {
-            let x = p.x + ox;
-            let y = p.y + oy;
+        for &(x, y) in p.cells().iter() {
             if !self.in_bounds(x, y) {
                 return true;
             }
@@ -68,30 +132,27 @@ impl Board {
     }
 
     pub fn lock_piece(&mut self, p: &ActivePiece) {
-        for (ox, oy) in blocks(p.t, p.rot) {
-            let x = p.x + ox;
-            let y = p.y + oy;
+        for (i, &(x, y)) in p.cells().iter().enumerate() {
             if self.in_bounds(x, y) {
-                self.set(x, y, Some(Cell { t: p.t, _power: false }));
+                let power = p.power.filter(|&(mino, _)| mino == i).map(|(_, power)| power);
+                self.set(x, y, Some(Cell { kind: CellKind::Piece(p.t), power }));
             }
         }
     }
 
-    /// Clears all full lines. Returns number of cleared lines.
-    pub fn clear_full_lines(&mut self) -> u32 {
-        let mut write_y = self.h - 1;
-        let mut cleared = 0u32;
+    fn row_full(&self, y: i16) -> bool {
+        (0..self.w).all(|x| self.get(x, y).is_some())
+    }
 
-        for read_y in (0..self.h).rev() {
-            let mut full = true;
-            for x in 0..self.w {
-                if self.get(x, read_y).is_none() {
-                    full = false;
-                    break;
-                }
-            }
-            if full {
-                cleared += 1;
+    /// Clears all full lines and fires the powers in them. A Nova also empties the
+    /// neighbouring cells in the surrounding rows before the stack collapses; powers
+    /// caught in a Nova blast (outside the cleared rows) are destroyed without firing.
+    pub fn clear_full_lines(&mut self) -> LineClear {
+        let (full, clear) = self.empty_full_rows();
+
+        let mut write_y = self.h - 1;
+        for read_y in (self.top()..self.h).rev() {
+            if full.contains(&read_y) {
                 continue; // skip copying this row (it's removed)
             }
             if write_y != read_y {
@@ -105,12 +166,347 @@ impl Board {
         }
 
         // Fill remaining top rows with empty
-        for y in 0..=write_y {
+        for y in self.top()..=write_y {
             for x in 0..self.w {
                 self.set(x, y, None);
             }
         }
 
-        cleared
+        clear
+    }
+
+    /// First step of a cascade (sticky) clear: empties the full lines and fires their
+    /// powers as `clear_full_lines` does, but leaves everything above hanging. `settle`
+    /// then resolves the chain one step at a time.
+    pub fn empty_full_lines(&mut self) -> LineClear {
+        self.empty_full_rows().1
+    }
+
+    /// Next cascade step: every connected group of cells falls as one until it lands,
+    /// then the lines completed by the fall are emptied. A clear of 0 lines ends the chain.
+    pub fn settle(&mut self) -> LineClear {
+        while self.drop_loose_groups() {}
+        self.empty_full_lines()
+    }
+
+    /// Empty the full rows, firing their powers; returns the rows and what they held.
+    fn empty_full_rows(&mut self) -> (Vec<i16>, LineClear) {
+        let full: Vec<i16> = (self.top()..self.h).filter(|&y| self.row_full(y)).collect();
+        let mut powers = Vec::new();
+        for &y in &full {
+            for x in 0..self.w {
+                if let Some(power) = self.get(x, y).and_then(|c| c.power) {
+                    powers.push(power);
+                    if power == Power::Nova {
+                        for (nx, ny) in [(x - 1, y - 1), (x, y - 1), (x + 1, y - 1), (x - 1, y + 1), (x, y + 1), (x + 1, y + 1)] {
+                            if !full.contains(&ny) {
+                                self.set(nx, ny, None);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for &y in &full {
+            for x in 0..self.w {
+                self.set(x, y, None);
+            }
+        }
+        let lines = full.len() as u32;
+        (full, LineClear { lines, powers })
+    }
+
+    /// Move every group of orthogonally connected cells with nothing under it down one
+    /// row. Groups are found before anything moves, so a falling group stays whole.
+    /// Returns false once everything rests on the floor or on another group.
+    fn drop_loose_groups(&mut self) -> bool {
+        let mut seen = vec![false; self.cells.len()];
+        let mut loose = Vec::new();
+        for y in self.top()..self.h {
+            for x in 0..self.w {
+                if seen[self.idx(x, y)] || self.get(x, y).is_none() {
+                    continue;
+                }
+                let group = self.group_at(x, y, &mut seen);
+                // Nothing below but empty cells or the group itself
+                if group.iter().all(|&(gx, gy)| {
+                    gy + 1 < self.h && (self.get(gx, gy + 1).is_none() || group.contains(&(gx, gy + 1)))
+                }) {
+                    loose.push(group);
+                }
+            }
+        }
+
+        // A loose group only falls into empty cells, so the groups cannot collide
+        let moving: Vec<_> = loose.iter().flatten().map(|&(x, y)| (x, y, self.get(x, y))).collect();
+        for &(x, y, _) in &moving {
+            self.set(x, y, None);
+        }
+        for &(x, y, cell) in &moving {
+            self.set(x, y + 1, cell);
+        }
+        !moving.is_empty()
+    }
+
+    /// Cells of the connected group containing (x, y), marking them in `seen`.
+    fn group_at(&self, x: i16, y: i16, seen: &mut [bool]) -> Vec<(i16, i16)> {
+        let mut group = Vec::new();
+        let mut stack = vec![(x, y)];
+        seen[self.idx(x, y)] = true;
+        while let Some((cx, cy)) = stack.pop() {
+            group.push((cx, cy));
+            for (nx, ny) in [(cx - 1, cy), (cx + 1, cy), (cx, cy - 1), (cx, cy + 1)] {
+                if self.get(nx, ny).is_some() && !seen[self.idx(nx, ny)] {
+                    seen[self.idx(nx, ny)] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        group
+    }
+
+    /// Push `rows` garbage rows up from the bottom, the first (lowest) with its hole in
+    /// column `hole`. The active piece is lifted with the stack, by up to `rows` rows, for
+    /// as long as it overlaps it.
+    pub fn push_garbage(
+        &mut self,
+        rows: u32,
+        hole: i16,
+        holes: GarbageHoles,
+        rng: &mut Pcg32,
+        active: Option<&mut ActivePiece>,
+    ) -> GarbageRise {
+        let rows = rows.min((self.h + self.hidden) as u32) as i16;
+        let overflow = (self.top()..self.top() + rows).any(|y| (0..self.w).any(|x| self.get(x, y).is_some()));
+        for y in self.top()..self.h - rows {
+            for x in 0..self.w {
+                let v = self.get(x, y + rows);
+                self.set(x, y, v);
+            }
+        }
+
+        let mut hole = hole.clamp(0, self.w - 1);
+        for (i, y) in (self.h - rows..self.h).rev().enumerate() {
+            if let GarbageHoles::Messy(chance) = holes {
+                if i > 0 && self.w > 1 && rng.chance(chance) {
+                    hole = (hole + 1 + rng.below(self.w as u32 - 1) as i16) % self.w;
+                }
+            }
+            for x in 0..self.w {
+                let cell = (x != hole).then_some(Cell { kind: CellKind::Garbage, power: None });
+                self.set(x, y, cell);
+            }
+        }
+
+        let mut piece_blocked = false;
+        if let Some(p) = active {
+            for _ in 0..rows {
+                if !self.collides(p) {
+                    break;
+                }
+                p.y -= 1;
+            }
+            piece_blocked = self.collides(p);
+        }
+        GarbageRise { overflow, piece_blocked }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::{PieceSet, RotationSystem};
+
+    fn filled() -> Option<Cell> {
+        Some(Cell { kind: CellKind::Piece(Piece::O), power: None })
+    }
+
+    #[test]
+    fn test_vanish_zone_is_in_bounds() {
+        let board = Board::new();
+        assert!(board.in_bounds(0, -1));
+        assert!(board.in_bounds(0, board.top()));
+        assert!(!board.in_bounds(0, board.top() - 1));
+        assert!(!board.in_bounds(0, board.h));
+    }
+
+    #[test]
+    fn test_should_pull_hidden_rows_into_view_when_clearing() {
+        let mut board = Board::new();
+        for x in 0..board.w {
+            board.set(x, board.h - 1, filled());
+        }
+        board.set(3, -1, filled());
+        assert_eq!(board.clear_full_lines().lines, 1);
+        assert!(board.get(3, 0).is_some());
+        assert!(board.get(3, -1).is_none());
+        assert!(board.get(0, board.h - 1).is_none());
+    }
+
+    #[test]
+    fn test_lock_piece_keeps_power_on_its_mino() {
+        let mut board = Board::new();
+        let mut p = ActivePiece::new(&PieceSet::standard(RotationSystem::Srs), Piece::I, 4, 10);
+        p.power = Some((2, Power::SlowTime));
+        board.lock_piece(&p);
+        assert_eq!(board.get(5, 10).unwrap().power, Some(Power::SlowTime));
+        assert_eq!(board.get(4, 10).unwrap().power, None);
+    }
+
+    #[test]
+    fn test_nova_clears_radius_one_before_collapse() {
+        let mut board = Board::new();
+        let bottom = board.h - 1;
+        for x in 0..board.w {
+            board.set(x, bottom, filled());
+        }
+        board.set(4, bottom, Some(Cell { kind: CellKind::Piece(Piece::T), power: Some(Power::Nova) }));
+        // Stack above the power cell: the row directly above is blasted, the one above that is not
+        for x in 2..=6 {
+            board.set(x, bottom - 1, filled());
+        }
+        board.set(4, bottom - 2, filled());
+
+        let clear = board.clear_full_lines();
+        assert_eq!(clear, LineClear { lines: 1, powers: vec![Power::Nova] });
+        let row: Vec<bool> = (0..board.w).map(|x| board.get(x, bottom).is_some()).collect();
+        assert_eq!(row, [false, false, true, false, false, false, true, false, false, false]);
+        assert!(board.get(4, bottom - 1).is_some());
+    }
+
+    #[test]
+    fn test_is_empty_after_clearing_last_row() {
+        let mut board = Board::new();
+        assert!(board.is_empty());
+        for x in 0..board.w {
+            board.set(x, board.h - 1, filled());
+        }
+        assert!(!board.is_empty());
+        board.clear_full_lines();
+        assert!(board.is_empty());
+    }
+
+    #[test]
+    fn test_with_size_clamps_to_supported_range() {
+        let board = Board::with_size(2, 60);
+        assert_eq!((board.w, board.h), (MIN_BOARD_W, MAX_BOARD_H));
+        let board = Board::with_size(4, 12);
+        assert!(board.in_bounds(3, 11));
+        assert!(!board.in_bounds(4, 0));
+    }
+
+    fn hole_column(board: &Board, y: i16) -> Option<i16> {
+        (0..board.w).find(|&x| board.get(x, y).is_none())
+    }
+
+    #[test]
+    fn test_clean_garbage_lifts_stack_and_keeps_one_hole() {
+        let mut board = Board::new();
+        let bottom = board.h - 1;
+        board.set(0, bottom, filled());
+        let mut rng = Pcg32::new(1, 0);
+        let rise = board.push_garbage(3, 7, GarbageHoles::Clean, &mut rng, None);
+        assert_eq!(rise, GarbageRise::default());
+        assert_eq!(board.get(0, bottom - 3).unwrap().kind, CellKind::Piece(Piece::O));
+        for y in bottom - 2..=bottom {
+            assert_eq!(hole_column(&board, y), Some(7));
+            assert_eq!(board.get(0, y).unwrap().kind, CellKind::Garbage);
+        }
+    }
+
+    #[test]
+    fn test_messy_garbage_moves_the_hole() {
+        let mut board = Board::new();
+        let mut rng = Pcg32::new(1, 0);
+        board.push_garbage(8, 2, GarbageHoles::Messy(1.0), &mut rng, None);
+        let holes: Vec<i16> = (board.h - 8..board.h).rev().filter_map(|y| hole_column(&board, y)).collect();
+        assert_eq!(holes.len(), 8);
+        assert_eq!(holes[0], 2);
+        // A certain move never leaves the hole where it was
+        assert!(holes.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn test_garbage_lifts_active_piece_and_reports_overflow() {
+        let mut board = Board::with_size(10, 10);
+        let set = PieceSet::standard(RotationSystem::Srs);
+        // Flat I resting on the floor
+        let mut p = ActivePiece::new(&set, Piece::I, 4, board.h - 1);
+        let mut rng = Pcg32::new(1, 0);
+        let rise = board.push_garbage(2, 0, GarbageHoles::Clean, &mut rng, Some(&mut p));
+        assert_eq!((rise, p.y), (GarbageRise::default(), board.h - 3));
+
+        // A cell at the very top of the vanish zone is pushed off the board
+        board.set(5, board.top(), filled());
+        let rise = board.push_garbage(1, 0, GarbageHoles::Clean, &mut rng, None);
+        assert!(rise.overflow);
+
+        // A piece on a stack reaching the top of the vanish zone has nowhere to go
+        let mut board = Board::with_size(10, 10);
+        for y in board.top() + 1..board.h {
+            board.set(4, y, filled());
+        }
+        let mut p = ActivePiece::new(&set, Piece::I, 5, board.top());
+        let rise = board.push_garbage(1, 0, GarbageHoles::Clean, &mut rng, Some(&mut p));
+        assert!(rise.piece_blocked);
+    }
+
+    #[test]
+    fn test_cascade_drops_groups_and_chains_new_lines() {
+        let mut board = Board::with_size(4, 10);
+        for x in 0..3 {
+            board.set(x, 9, filled());
+        }
+        for x in 0..4 {
+            board.set(x, 8, filled());
+        }
+        // Hangs off the full row, then falls into the hole below it
+        board.set(3, 7, filled());
+        assert_eq!(board.empty_full_lines().lines, 1);
+        assert!(board.get(3, 7).is_some());
+        assert_eq!(board.settle().lines, 1);
+        assert_eq!(board.settle().lines, 0);
+        assert!(board.is_empty());
+
+        // Without a full line nothing moves
+        board.set(1, 5, filled());
+        assert_eq!(board.empty_full_lines().lines, 0);
+        assert!(board.get(1, 5).is_some());
+    }
+
+    fn occupied(board: &Board) -> Vec<(i16, i16)> {
+        (board.top()..board.h)
+            .flat_map(|y| (0..board.w).map(move |x| (x, y)))
+            .filter(|&(x, y)| board.get(x, y).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn test_cascade_keeps_connected_groups_together() {
+        let mut board = Board::with_size(4, 10);
+        for x in 0..4 {
+            board.set(x, 9, filled());
+        }
+        board.set(0, 8, filled());
+        for (x, y) in [(1, 7), (2, 7), (2, 6)] {
+            board.set(x, y, filled());
+        }
+        board.empty_full_lines();
+        assert_eq!(board.settle().lines, 0);
+        assert_eq!(occupied(&board), [(2, 8), (0, 9), (1, 9), (2, 9)]);
+    }
+
+    #[test]
+    fn test_cascade_lands_s_overhang_in_one_piece() {
+        let mut board = Board::with_size(4, 10);
+        for x in 0..4 {
+            board.set(x, 9, filled());
+        }
+        for (x, y) in [(0, 5), (1, 5), (1, 4), (2, 4)] {
+            board.set(x, y, filled());
+        }
+        board.empty_full_lines();
+        board.settle();
+        assert_eq!(occupied(&board), [(1, 8), (2, 8), (0, 9), (1, 9)]);
     }
 }
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use super::timestep::TICK_DT;
+
+/// Fastest meaningful gravity: 20 rows per 60 Hz frame ("20G"), i.e. instant drop.
+pub const TWENTY_G: f32 = 1.0 / (60.0 * 20.0);
+
+/// Guideline gravity curve: seconds per row for a 0-based `level`
+/// (`(0.8 - (L - 1) * 0.007)^(L - 1)` with `L = level + 1`).
+/// Levels at or past the 20G threshold are clamped to `TWENTY_G`.
+pub fn seconds_per_row(level: u32) -> f32 {
+    let l = level.min(19) as f32; // curve bottoms out before level 20 anyway
+    (0.8 - l * 0.007).powf(l).max(TWENTY_G)
+}
+
+/// Gravity by level, as chosen by a rule set.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GravityCurve {
+    /// The guideline formula, see `seconds_per_row`.
+    #[default]
+    Guideline,
+    /// Seconds per row for levels 0, 1, 2, ...; the last entry holds for every later level.
+    Table(Vec<f32>),
+    /// Like `Table`, in 60 Hz frames per row (see `NES_FRAMES`).
+    Frames(Vec<u32>),
+}
+
+/// NES frames per row for levels 0 to 29; level 29 and above stay at 1 frame ("kill screen").
+pub const NES_FRAMES: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1,
+];
+
+impl GravityCurve {
+    pub fn seconds_per_row(&self, level: u32) -> f32 {
+        match self {
+            GravityCurve::Guideline => seconds_per_row(level),
+            GravityCurve::Table(table) => match table.last() {
+                Some(&last) => table.get(level as usize).copied().unwrap_or(last).max(TWENTY_G),
+                None => seconds_per_row(level),
+            },
+            GravityCurve::Frames(frames) => match frames.last() {
+                Some(&last) => frames.get(level as usize).copied().unwrap_or(last) as f32 * TICK_DT,
+                None => seconds_per_row(level),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guideline_curve_values() {
+        let expected = [(0, 1.0), (1, 0.793), (2, 0.6178), (4, 0.3552), (9, 0.0642), (14, 0.00706)];
+        for (level, secs) in expected {
+            let got = seconds_per_row(level);
+            assert!((got - secs).abs() < secs * 0.01, "level {}: {} vs {}", level + 1, got, secs);
+        }
+    }
+
+    #[test]
+    fn test_gravity_never_slows_down_and_reaches_20g() {
+        for level in 1..40 {
+            assert!(seconds_per_row(level) <= seconds_per_row(level - 1));
+        }
+        assert_eq!(seconds_per_row(19), TWENTY_G);
+        assert_eq!(seconds_per_row(100), TWENTY_G);
+    }
+
+    #[test]
+    fn test_table_curve_holds_last_entry() {
+        let curve = GravityCurve::Table(vec![1.0, 0.5, 0.25]);
+        assert_eq!(curve.seconds_per_row(1), 0.5);
+        assert_eq!(curve.seconds_per_row(30), 0.25);
+        assert_eq!(GravityCurve::Table(vec![]).seconds_per_row(4), seconds_per_row(4));
+    }
+
+    #[test]
+    fn test_nes_frames_curve() {
+        let curve = GravityCurve::Frames(NES_FRAMES.to_vec());
+        assert_eq!(curve.seconds_per_row(0), 48.0 * TICK_DT);
+        assert_eq!(curve.seconds_per_row(19), 2.0 * TICK_DT);
+        assert_eq!(curve.seconds_per_row(40), TICK_DT);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+/// Soft drop factors at or above this drop the piece straight to the stack.
+pub const SDF_INSTANT: f32 = 41.0;
+
+/// Player-tunable movement timings (persisted in settings).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handling {
+    /// Delayed auto shift: seconds a direction must be held before it repeats.
+    pub das: f32,
+    /// Auto repeat rate: seconds between repeated shifts. 0 shifts straight to the wall.
+    pub arr: f32,
+    /// Soft drop factor: soft drop falls this many times faster than gravity.
+    pub sdf: f32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: 10.0 / 60.0,
+            arr: 2.0 / 60.0,
+            sdf: 20.0,
+        }
+    }
+}
+
+/// Steps produced by a single `AutoShift::tick`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shift {
+    pub dir: i16,
+    /// Cells to move; `u32::MAX` means "until blocked" (ARR 0).
+    pub steps: u32,
+}
+
+/// DAS/ARR state for horizontal movement. When both directions are held the most
+/// recently pressed one wins; releasing it hands control back to the other.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutoShift {
+    left: bool,
+    right: bool,
+    dir: i16,
+    charge: f32, // seconds the current direction has been held
+    repeat: f32, // time banked towards the next ARR step
+}
+
+impl AutoShift {
+    pub fn tick(&mut self, handling: &Handling, dt: f32, left: bool, right: bool) -> Shift {
+        let left_pressed = left && !self.left;
+        let right_pressed = right && !self.right;
+        self.left = left;
+        self.right = right;
+
+        let mut fresh = false;
+        if right_pressed {
+            self.dir = 1;
+            fresh = true;
+        }
+        if left_pressed {
+            self.dir = -1;
+            fresh = true;
+        }
+        let dir_held = (self.dir < 0 && left) || (self.dir > 0 && right);
+        if !fresh && !dir_held {
+            // Active direction released: fall back to the other one if it is still held
+            self.dir = if left { -1 } else if right { 1 } else { 0 };
+            fresh = self.dir != 0;
+        }
+
+        if self.dir == 0 {
+            return Shift { dir: 0, steps: 0 };
+        }
+        if fresh {
+            self.charge = 0.0;
+            self.repeat = 0.0;
+            return Shift { dir: self.dir, steps: 1 };
+        }
+
+        let before = self.charge;
+        self.charge += dt;
+        if self.charge < handling.das {
+            return Shift { dir: self.dir, steps: 0 };
+        }
+        if handling.arr <= 0.0 {
+            return Shift { dir: self.dir, steps: u32::MAX };
+        }
+
+        // Crossing the DAS threshold shifts once; leftover time feeds the repeat timer.
+        let mut steps = 0;
+        if before < handling.das {
+            steps += 1;
+            self.repeat = self.charge - handling.das;
+        } else {
+            self.repeat += dt;
+        }
+        while self.repeat >= handling.arr {
+            self.repeat -= handling.arr;
+            steps += 1;
+        }
+        Shift { dir: self.dir, steps }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handling() -> Handling {
+        Handling { das: 0.125, arr: 0.0625, sdf: 20.0 }
+    }
+
+    #[test]
+    fn test_press_moves_once_then_waits_for_das() {
+        let h = handling();
+        let mut shift = AutoShift::default();
+        assert_eq!(shift.tick(&h, 0.0, true, false), Shift { dir: -1, steps: 1 });
+        assert_eq!(shift.tick(&h, 0.0625, true, false).steps, 0);
+        assert_eq!(shift.tick(&h, 0.0625, true, false).steps, 1);
+        assert_eq!(shift.tick(&h, 0.0625, true, false).steps, 1);
+        assert_eq!(shift.tick(&h, 0.125, true, false).steps, 2);
+    }
+
+    #[test]
+    fn test_zero_arr_shifts_to_wall() {
+        let h = Handling { arr: 0.0, ..handling() };
+        let mut shift = AutoShift::default();
+        shift.tick(&h, 0.0, false, true);
+        assert_eq!(shift.tick(&h, 0.125, false, true), Shift { dir: 1, steps: u32::MAX });
+    }
+
+    #[test]
+    fn test_last_pressed_direction_wins() {
+        let h = handling();
+        let mut shift = AutoShift::default();
+        shift.tick(&h, 0.0, true, false);
+        assert_eq!(shift.tick(&h, 0.0, true, true), Shift { dir: 1, steps: 1 });
+        assert_eq!(shift.tick(&h, 0.0625, true, true).dir, 1);
+        // Releasing right hands control back to the still-held left, with a fresh DAS
+        assert_eq!(shift.tick(&h, 0.0625, true, false), Shift { dir: -1, steps: 1 });
+        assert_eq!(shift.tick(&h, 0.0625, true, false).steps, 0);
+    }
+
+    #[test]
+    fn test_release_stops_movement() {
+        let h = handling();
+        let mut shift = AutoShift::default();
+        shift.tick(&h, 0.0, true, false);
+        shift.tick(&h, 0.25, true, false);
+        assert_eq!(shift.tick(&h, 0.25, false, false), Shift { dir: 0, steps: 0 });
+    }
+}
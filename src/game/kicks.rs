@@ -0,0 +1,93 @@
+use super::piece::{Piece, Rot, RotationSystem};
+
+/// Kick offsets are listed as in the guideline tables: +x is right, +y is up.
+/// `rotation_kicks` flips y so they can be added straight onto board coordinates.
+type KickRow = [(i16, i16); 5];
+
+/// A piece's own 90° kick tests: eight rows in the transition order below, each listing
+/// offsets (+y up) to try in order.
+pub type KickTable = Vec<Vec<(i16, i16)>>;
+
+// Transition order: 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L
+const JLSTZ_KICKS: [KickRow; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+];
+
+const I_KICKS: [KickRow; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+];
+
+/// 180° kicks (SRS+ as used by Tetr.io), shared by every piece.
+/// Order: 0->2, R->L, 2->0, L->R
+type FlipRow = [(i16, i16); 6];
+const FLIP_KICKS: [FlipRow; 4] = [
+    [(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+    [(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+];
+
+fn transition(from: Rot, to: Rot) -> Option<usize> {
+    match (from, to) {
+        (Rot::R0, Rot::R90) => Some(0),
+        (Rot::R90, Rot::R0) => Some(1),
+        (Rot::R90, Rot::R180) => Some(2),
+        (Rot::R180, Rot::R90) => Some(3),
+        (Rot::R180, Rot::R270) => Some(4),
+        (Rot::R270, Rot::R180) => Some(5),
+        (Rot::R270, Rot::R0) => Some(6),
+        (Rot::R0, Rot::R270) => Some(7),
+        _ => None,
+    }
+}
+
+/// Test offsets for a 180° rotation out of `from`, in board coordinates. Pieces whose
+/// flipped pose is identical (O) always pass the first test, so no exception is needed.
+pub fn flip_kicks(from: Rot) -> FlipRow {
+    FLIP_KICKS[from as usize].map(|(dx, dy)| (dx, -dy))
+}
+
+/// Simplified ARS kicks: try in place, then one column right, then one left. The I piece
+/// never kicks. The centre-column rule of the real games is not modelled.
+const ARS_KICKS: [(i16, i16); 3] = [(0, 0), (1, 0), (-1, 0)];
+
+/// Per-piece tables of the standard set where a piece differs from its system's default:
+/// the SRS I table, and no kicks for the ARS I and for O.
+pub fn standard_kicks(sys: RotationSystem, t: Piece) -> Option<KickTable> {
+    match (sys, t) {
+        (RotationSystem::Srs, Piece::I) => Some(I_KICKS.iter().map(|row| row.to_vec()).collect()),
+        (RotationSystem::Srs | RotationSystem::Ars, Piece::O) | (RotationSystem::Ars, Piece::I) => {
+            Some(vec![vec![(0, 0)]; 8])
+        }
+        _ => None,
+    }
+}
+
+/// Test offsets for a 90° rotation, in board coordinates: the piece's own `table` if it has
+/// one, else the default of `sys` (SRS JLSTZ table, ARS, or none for NRS).
+pub fn rotation_kicks(sys: RotationSystem, table: Option<&KickTable>, from: Rot, to: Rot) -> Vec<(i16, i16)> {
+    let Some(i) = transition(from, to) else {
+        return vec![(0, 0)];
+    };
+    let row = match (table, sys) {
+        (Some(table), _) => table[i].clone(),
+        (None, RotationSystem::Srs) => JLSTZ_KICKS[i].to_vec(),
+        (None, RotationSystem::Ars) => ARS_KICKS.to_vec(),
+        (None, RotationSystem::Nrs) => vec![(0, 0)],
+    };
+    row.into_iter().map(|(dx, dy)| (dx, -dy)).collect()
+}
@@ -1,17 +1,61 @@
 pub mod piece;
 pub mod random;
+pub mod rng;
 pub mod board;
+pub mod kicks;
+pub mod handling;
+pub mod gravity;
+pub mod scoring;
+pub mod rules;
+pub mod preset;
+pub mod timestep;
 
-use piece::{ActivePiece, Rot, Tetromino};
-use random::SevenBag;
-use board::Board;
+use piece::{ActivePiece, Piece, PieceSet, Rot, RotationSystem};
+use handling::{AutoShift, Handling, SDF_INSTANT};
+use scoring::{ClearKind, ComboChain, Streaks};
+use random::Randomizer;
+use board::{Board, GarbageHoles, Power};
+use rng::Pcg32;
+use rules::RuleSet;
 
-/// Board dimensions (playable rows only; no hidden spawn rows yet)
+/// Default board dimensions (visible rows)
 pub const BOARD_W: i16 = 10;
 pub const BOARD_H: i16 = 20;
+/// Board sizes that can be picked at runtime.
+pub const MIN_BOARD_W: i16 = 4;
+pub const MAX_BOARD_W: i16 = 20;
+pub const MIN_BOARD_H: i16 = 10;
+pub const MAX_BOARD_H: i16 = 40;
+/// Highest level that can be picked to start on (NES level select).
+pub const MAX_START_LEVEL: u32 = 19;
+/// Vanish zone rows stacked above the visible area (guideline buffer).
+pub const BOARD_HIDDEN_H: i16 = 20;
 
-/// Simple input snapshot from the UI layer.
-/// We keep this minimal for the first playable shell.
+/// Default seconds a grounded piece may rest before it locks.
+pub const DEFAULT_LOCK_DELAY: f32 = 0.5;
+/// Move/rotate resets allowed per piece before the lock timer stops resetting.
+pub const MAX_LOCK_RESETS: u32 = 15;
+
+/// Chance that a spawned piece carries a power mino.
+pub const POWER_CHANCE: f64 = 0.05;
+/// Seconds gravity stays halved after a Slow Time cell is cleared.
+pub const SLOW_TIME_SECS: f32 = 5.0;
+
+/// PCG stream for power rolls, so they don't mirror the piece generator's draws.
+const POWER_STREAM: u64 = 1;
+
+/// PCG stream for garbage hole columns.
+const GARBAGE_STREAM: u64 = 2;
+
+/// Seconds each cascade step stays on screen before the stack settles into the next.
+pub const CASCADE_STEP_SECS: f32 = 0.3;
+
+/// Seconds a callout stays on screen.
+pub const CALLOUT_SECS: f32 = 1.5;
+
+/// Snapshot of which buttons are currently held, sampled once per update.
+/// `Game` compares it with the previous snapshot to find fresh presses, so
+/// OS key-repeat never reaches the simulation.
 #[derive(Default, Debug, Clone, Copy)]
 pub struct GameInput {
     pub left: bool,
@@ -20,165 +64,746 @@ pub struct GameInput {
     pub hard_drop: bool,
     pub rot_cw: bool,
     pub rot_ccw: bool,
+    pub rot_180: bool,
+    pub hold: bool,
     pub pause: bool,
     pub restart: bool,
 }
 
+impl GameInput {
+    /// Buttons held in either snapshot; used to latch taps that fall between two ticks.
+    pub fn merge(self, other: GameInput) -> GameInput {
+        GameInput {
+            left: self.left || other.left,
+            right: self.right || other.right,
+            soft_drop: self.soft_drop || other.soft_drop,
+            hard_drop: self.hard_drop || other.hard_drop,
+            rot_cw: self.rot_cw || other.rot_cw,
+            rot_ccw: self.rot_ccw || other.rot_ccw,
+            rot_180: self.rot_180 || other.rot_180,
+            hold: self.hold || other.hold,
+            pause: self.pause || other.pause,
+            restart: self.restart || other.restart,
+        }
+    }
+
+    /// Buttons held now that were not held in `prev`.
+    fn pressed_since(self, prev: GameInput) -> GameInput {
+        GameInput {
+            left: self.left && !prev.left,
+            right: self.right && !prev.right,
+            soft_drop: self.soft_drop && !prev.soft_drop,
+            hard_drop: self.hard_drop && !prev.hard_drop,
+            rot_cw: self.rot_cw && !prev.rot_cw,
+            rot_ccw: self.rot_ccw && !prev.rot_ccw,
+            rot_180: self.rot_180 && !prev.rot_180,
+            hold: self.hold && !prev.hold,
+            pause: self.pause && !prev.pause,
+            restart: self.restart && !prev.restart,
+        }
+    }
+}
+
+/// Why the game ended (guideline top-out conditions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOut {
+    /// A new piece spawned overlapping the stack.
+    BlockOut,
+    /// A piece locked entirely inside the vanish zone.
+    LockOut,
+    /// Garbage pushed the stack, or the piece in play, past the top of the vanish zone.
+    Overflow,
+}
+
+/// Transient announcement for the UI (e.g. "T-SPIN DOUBLE").
+#[derive(Debug, Clone)]
+pub struct Callout {
+    pub lines: Vec<String>,
+    pub age: f32, // seconds since it was raised; expires at CALLOUT_SECS
+    /// The lock emptied the matrix; the UI shows a "PERFECT CLEAR" banner.
+    pub perfect_clear: bool,
+}
+
+/// A cascade clear being resolved step by step.
+#[derive(Debug, Clone, Copy)]
+struct Cascade {
+    step: u32, // steps cleared so far, the lock's own clear being the first
+    spawn_delay: f32, // entry delay to run once the chain ends
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Playing,
+    GameOver(TopOut),
+}
+
 pub struct Game {
     pub paused: bool,
+    phase: Phase,
+    elapsed: f32, // seconds of unpaused play
     pub score: u64,
     pub level: u32,
+    start_level: u32,
     pub lines: u32,
+    pub handling: Handling,
+    /// Rotation, lock, hold, scoring and timing rules; see `with_rules`.
+    pub rules: RuleSet,
+    /// Restart (R) replays the same seed instead of rolling a new one.
+    pub reuse_seed: bool,
+    seed: u64,
 
     active: ActivePiece,
-    bag: SevenBag,
+    pieces: PieceSet,
+    hold: Option<Piece>,
+    hold_power: Option<(usize, Power)>, // power the held piece keeps until it comes back
+    hold_available: bool, // one swap per drop; re-armed by spawn_next
+    entry_timer: Option<f32>, // seconds left before the next piece enters; None while a piece is in play
+    bag: Box<dyn Randomizer>,
     board: Board,
-    gravity_interval: f32, // seconds per row
-    base_gravity_interval: f32, // base gravity before pulse effects
-    acc: f32,
+    lock_timer: f32,
+    lock_resets: u32,
+    lowest_y: i16, // lowest pivot row reached by the current piece
+    last_rotation: Option<bool>, // whether the final kick test was used, if the last successful action was a rotation
+    streaks: Streaks,
+    callout: Option<Callout>,
+    acc: f32, // gravity progress towards the next row
     pulse_time: f32, // time tracker for gravity pulse
     pulse_enabled: bool,
+    chain: ComboChain,
+    chain_enabled: bool,
+    cascade_enabled: bool, // sticky gravity with chain reactions after a clear
+    cascade: Option<Cascade>, // chain in progress; steps resolve while the entry timer runs
+    power_rng: Pcg32,
+    garbage_rng: Pcg32,
+    slow_time: f32, // seconds of Slow Time left
+    prev_input: GameInput,
+    spawn_presses: GameInput, // buttons pressed since the last lock, for IRS/IHS
+    shift: AutoShift,
+    soft_acc: f32, // soft drop progress towards the next row
 }
 
 impl Game {
-    pub fn new() -> Self {
-        let mut bag = SevenBag::new(None);
-        let board = Board::new();
-        let active = Self::spawn(&mut bag, &board);
-        let base_gravity = 0.8;
-        Self {
+    /// New game on a random seed. `randomizer` picks the generator; see `with_seed`.
+    pub fn new(randomizer: Box<dyn Randomizer>) -> Self {
+        Self::with_seed(randomizer, random::random_seed())
+    }
+
+    /// New game whose piece sequence and power rolls are fully determined by `seed`.
+    /// `randomizer` only chooses the kind of generator; it is restarted from `seed`.
+    pub fn with_seed(randomizer: Box<dyn Randomizer>, seed: u64) -> Self {
+        let mut bag = randomizer.reset(Some(seed));
+        let pieces = PieceSet::standard(RotationSystem::Srs);
+        let first = bag.next();
+        let mut game = Self {
             paused: false,
+            phase: Phase::Playing,
+            elapsed: 0.0,
             score: 0,
             level: 0,
+            start_level: 0,
             lines: 0,
-            active,
+            handling: Handling::default(),
+            rules: RuleSet::default(),
+            reuse_seed: false,
+            seed,
+            active: ActivePiece::new(&pieces, first, 0, 0),
+            pieces,
+            hold: None,
+            hold_power: None,
+            hold_available: true,
+            entry_timer: None,
             bag,
-            board,
-            gravity_interval: base_gravity,
-            base_gravity_interval: base_gravity,
+            board: Board::new(),
+            lock_timer: 0.0,
+            lock_resets: 0,
+            lowest_y: 0,
+            last_rotation: None,
+            streaks: Streaks::new(),
+            callout: None,
             acc: 0.0,
             pulse_time: 0.0,
             pulse_enabled: false,
+            chain: ComboChain::new(),
+            chain_enabled: false,
+            cascade_enabled: false,
+            cascade: None,
+            power_rng: Pcg32::new(seed, POWER_STREAM),
+            garbage_rng: Pcg32::new(seed, GARBAGE_STREAM),
+            slow_time: 0.0,
+            prev_input: GameInput::default(),
+            spawn_presses: GameInput::default(),
+            shift: AutoShift::default(),
+            soft_acc: 0.0,
+        };
+        game.spawn_piece(first);
+        game.reset_lock_state();
+        game
+    }
+
+    /// Start over with a fresh board, keeping player configuration and held-key state.
+    /// The seed is kept when `reuse_seed` is set.
+    fn restart(&mut self) {
+        let seed = if self.reuse_seed { self.seed } else { random::random_seed() };
+        let mut next = Self::with_seed(self.bag.reset(None), seed)
+            .with_board_size(self.board.w, self.board.h)
+            .with_rules(self.rules.clone())
+            .with_pieces(self.pieces.clone())
+            .with_start_level(self.start_level);
+        next.reuse_seed = self.reuse_seed;
+        next.handling = self.handling;
+        next.set_pulse_gravity(self.pulse_enabled);
+        next.set_combo_chain(self.chain_enabled);
+        next.set_cascade(self.cascade_enabled);
+        next.prev_input = self.prev_input;
+        next.shift = self.shift;
+        *self = next;
+    }
+
+    /// Play on an empty `w` x `h` board (clamped to the supported sizes). Meant for a game
+    /// that has not started yet: the first piece is re-spawned on the new board.
+    pub fn with_board_size(mut self, w: i16, h: i16) -> Self {
+        self.board = Board::with_size(w, h);
+        self.respawn_first_piece();
+        self
+    }
+
+    /// Play under `rules`. Like `with_board_size`, meant for a game that has not started:
+    /// the first piece is re-spawned in the rule set's rotation system. The randomizer is
+    /// left alone; callers pick it from `rules.randomizer` when building the game.
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        if self.pieces.standard {
+            self.pieces = PieceSet::standard(self.rules.rotation);
         }
+        self.respawn_first_piece();
+        if self.rules.start_garbage > 0 {
+            let hole = self.garbage_rng.index(self.board.w as usize) as i16;
+            self.push_garbage(self.rules.start_garbage, hole, self.rules.garbage_holes);
+        }
+        self
+    }
+
+    /// Deal from `pieces` instead of the standard tetrominoes. Like `with_rules`, meant for a
+    /// game that has not started; the randomizer must already deal from a set of this size.
+    pub fn with_pieces(mut self, pieces: PieceSet) -> Self {
+        self.pieces = if pieces.standard { PieceSet::standard(self.rules.rotation) } else { pieces };
+        self.respawn_first_piece();
+        self
+    }
+
+    fn respawn_first_piece(&mut self) {
+        let power = self.active.power;
+        self.active = self.spawn(self.active.t);
+        self.active.power = power;
+        self.reset_lock_state();
+    }
+
+    /// Begin at `level` (level select); how later levels follow is up to the rules.
+    pub fn with_start_level(mut self, level: u32) -> Self {
+        self.start_level = level;
+        self.level = level;
+        self
+    }
+
+    /// Handling in effect: the rule set's when it imposes one, else the player's.
+    fn handling(&self) -> Handling {
+        self.rules.handling.unwrap_or(self.handling)
     }
 
     pub fn set_pulse_gravity(&mut self, enabled: bool) {
         self.pulse_enabled = enabled;
+    }
+
+    /// Toggle the optional timed combo chain multiplier.
+    pub fn set_combo_chain(&mut self, enabled: bool) {
+        self.chain_enabled = enabled;
         if !enabled {
-            // Reset to base gravity when disabled
-            self.gravity_interval = self.base_gravity_interval;
+            self.chain = ComboChain::new();
         }
     }
 
-    fn spawn(bag: &mut SevenBag, board: &Board) -> ActivePiece {
-        let t = bag.next();
-        // Try to spawn centered. If initial pose is out of bounds due to offsets,
-        // nudge downward a bit so it's visible.
-        let mut p = ActivePiece::new(t, BOARD_W / 2, 0);
-        // Adjust downwards up to 3 rows to get on-screen if some shapes have negative offsets.
-        for _ in 0..3 {
-            if !board.collides(&p) {
-                break;
-            }
-            p.y += 1;
+    /// Toggle cascade clearing: after a clear, connected groups fall until they land and
+    /// any lines they complete clear as further chain steps.
+    pub fn set_cascade(&mut self, enabled: bool) {
+        self.cascade_enabled = enabled;
+    }
+
+    /// Current seconds per row: the level's curve value, modulated by pulse mode and Slow Time.
+    fn gravity_interval(&self) -> f32 {
+        let mut base = self.rules.gravity.seconds_per_row(self.level);
+        if self.slow_time > 0.0 {
+            base *= 2.0;
+        }
+        if self.pulse_enabled {
+            // Sine wave oscillation with 8 second period (2π / 8 = π/4)
+            let pulse_factor = (self.pulse_time * std::f32::consts::PI / 4.0).sin() * 0.5 + 1.0; // Oscillates between 0.5 and 1.5
+            base * pulse_factor
+        } else {
+            base
+        }
+    }
+
+    /// Guideline spawn: I and O centered, JLSTZ in the left-middle columns, bottom row
+    /// in row 21 (just above the skyline). The piece then drops one row if nothing blocks it.
+    /// NES pieces spawn one column further right, as on the original.
+    fn spawn(&self, t: Piece) -> ActivePiece {
+        let x = match self.rules.rotation {
+            RotationSystem::Nrs => self.board.w / 2,
+            _ => (self.board.w - 1) / 2,
+        };
+        let mut p = ActivePiece::new(&self.pieces, t, x, -1);
+        let mut below = p;
+        below.y += 1;
+        if !self.board.collides(&p) && !self.board.collides(&below) {
+            p = below;
         }
         p
     }
 
+    /// Roll whether a freshly spawned piece carries a power, and on which of its `minos`.
+    fn roll_power(rng: &mut Pcg32, minos: usize) -> Option<(usize, Power)> {
+        if !rng.chance(POWER_CHANCE) {
+            return None;
+        }
+        let power = if rng.below(2) == 0 { Power::Nova } else { Power::SlowTime };
+        Some((rng.index(minos), power))
+    }
+
+    /// Spawn `t` at the top with a freshly rolled power.
+    fn spawn_piece(&mut self, t: Piece) {
+        self.active = self.spawn(t);
+        self.active.power = Self::roll_power(&mut self.power_rng, self.active.minos.len());
+    }
+
     #[inline]
     fn can_place(&self, p: &ActivePiece) -> bool {
         !self.board.collides(p)
     }
 
+    /// Whether the active piece is resting on the stack or floor.
+    fn grounded(&self) -> bool {
+        let mut below = self.active;
+        below.y += 1;
+        self.board.collides(&below)
+    }
+
     fn try_move(&mut self, dx: i16, dy: i16) -> bool {
         let mut np = self.active;
         np.x += dx;
         np.y += dy;
         if self.can_place(&np) {
+            let grounded = self.grounded();
             self.active = np;
+            self.last_rotation = None;
+            self.on_piece_moved(grounded);
             true
         } else {
             false
         }
     }
 
+    /// Rotate 90°: try each of the rotation system's kick offsets in order and keep the
+    /// first pose that fits (only the unkicked pose when the rules disable kicks).
     fn try_rotate(&mut self, rot: Rot) -> bool {
-        let mut np = self.active;
-        np.rot = rot;
-        if self.can_place(&np) {
-            self.active = np;
+        let table = self.pieces.get(self.active.t).kicks.as_ref();
+        let tests = kicks::rotation_kicks(self.rules.rotation, table, self.active.rot, rot);
+        let tests = if self.rules.kicks { &tests[..] } else { &tests[..1] };
+        match self.rotate_with(rot, tests) {
+            Some(i) => {
+                self.last_rotation = Some(i > 0 && i == tests.len() - 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rotate 180° using the separate flip table (or in place only when it is disabled).
+    fn try_flip(&mut self) -> bool {
+        if !self.rules.rotate_180 {
+            return false;
+        }
+        let tests = kicks::flip_kicks(self.active.rot);
+        let tests = if self.rules.flip_kicks { &tests[..] } else { &tests[..1] };
+        if self.rotate_with(self.active.rot.flip(), tests).is_some() {
+            // Counts as a rotation for T-spins, but never as the final-kick upgrade
+            self.last_rotation = Some(false);
             true
         } else {
             false
         }
     }
 
+    /// Move to the first kicked pose of `rot` that fits; returns the test index used.
+    fn rotate_with(&mut self, rot: Rot, tests: &[(i16, i16)]) -> Option<usize> {
+        for (i, &(dx, dy)) in tests.iter().enumerate() {
+            let mut np = self.active.rotated(&self.pieces, rot);
+            np.x += dx;
+            np.y += dy;
+            if self.can_place(&np) {
+                let grounded = self.grounded();
+                self.active = np;
+                self.on_piece_moved(grounded);
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Guideline "move reset": reaching a new lowest row restores the full reset budget;
+    /// otherwise a move or rotation made while grounded restarts the lock timer, at most
+    /// `rules.lock_resets` times per piece.
+    fn on_piece_moved(&mut self, was_grounded: bool) {
+        if self.active.y > self.lowest_y {
+            self.lowest_y = self.active.y;
+            self.lock_resets = 0;
+            self.lock_timer = 0.0;
+        } else if was_grounded && self.lock_resets < self.rules.lock_resets {
+            self.lock_resets += 1;
+            self.lock_timer = 0.0;
+        }
+    }
+
+    fn reset_lock_state(&mut self) {
+        self.lock_timer = 0.0;
+        self.lock_resets = 0;
+        self.lowest_y = self.active.y;
+        self.last_rotation = None;
+    }
+
+    /// Swap the active piece into the hold slot. The outgoing piece (or the next one from the
+    /// bag on first use) respawns at the top. Only one swap is allowed per drop.
+    fn try_hold(&mut self) -> bool {
+        if !self.rules.hold || !self.hold_available {
+            return false;
+        }
+        self.swap_hold(self.active.t, self.active.power);
+        self.hold_available = false;
+        self.acc = 0.0;
+        self.reset_lock_state();
+        if self.board.collides(&self.active) {
+            self.phase = Phase::GameOver(TopOut::BlockOut);
+        }
+        true
+    }
+
+    /// Put `t` and its `power` in the hold slot. The piece it replaces comes back with the
+    /// power it went in with, so holding never re-rolls one; an empty slot deals a new piece.
+    fn swap_hold(&mut self, t: Piece, power: Option<(usize, Power)>) {
+        let out_power = std::mem::replace(&mut self.hold_power, power);
+        match self.hold.replace(t) {
+            Some(held) => {
+                self.active = self.spawn(held);
+                self.active.power = out_power;
+            }
+            None => {
+                let next = self.bag.next();
+                self.spawn_piece(next);
+            }
+        }
+    }
+
+    /// Move down one row for soft drop, scoring the row if the piece actually fell.
+    fn soft_drop_step(&mut self) -> bool {
+        let moved = self.try_move(0, 1);
+        if moved {
+            self.score += self.rules.scoring.soft_drop as u64;
+        }
+        moved
+    }
+
     fn lock_and_spawn(&mut self) {
+        let lock_out = self.active.cells().iter().all(|&(_, y)| y < 0);
+        let spin = if self.rules.scoring.t_spins && self.pieces.get(self.active.t).t_spin {
+            scoring::t_spin(&self.board, &self.active, self.last_rotation)
+        } else {
+            scoring::Spin::None
+        };
         self.board.lock_piece(&self.active);
-        let cleared = self.board.clear_full_lines();
+        if lock_out {
+            self.phase = Phase::GameOver(TopOut::LockOut);
+            return;
+        }
+        let line_clear = if self.cascade_enabled {
+            self.board.empty_full_lines()
+        } else {
+            self.board.clear_full_lines()
+        };
+        let cleared = line_clear.lines;
+        let clear = ClearKind { lines: cleared, spin };
+        let award = self.streaks.score(clear, self.level as u64 + 1, &self.rules.scoring);
+        let chain = if self.chain_enabled && cleared > 0 {
+            self.chain.on_clear()
+        } else {
+            1
+        };
+        self.score += award.points * chain as u64;
+        let perfect_clear = self.rules.scoring.perfect_clear && cleared > 0 && self.board.is_empty();
+        if perfect_clear {
+            let back_to_back = award.back_to_back && cleared >= 4;
+            self.score += scoring::perfect_clear_points(cleared, back_to_back) as u64 * (self.level as u64 + 1);
+        }
+        let mut lines: Vec<String> = clear.name().into_iter().collect();
+        if award.back_to_back {
+            lines.push("BACK-TO-BACK".to_owned());
+        }
+        if award.combo > 0 {
+            lines.push(format!("COMBO ×{}", award.combo));
+        }
+        if chain > 1 {
+            lines.push(format!("CHAIN ×{}", chain));
+        }
+        self.fire_powers(line_clear.powers, &mut lines);
+        if !lines.is_empty() || perfect_clear {
+            self.callout = Some(Callout { lines, age: 0.0, perfect_clear });
+        }
+        self.add_lines(cleared);
+        let mut delay = self.rules.entry_delay;
+        if self.rules.entry_delay_by_height {
+            delay += entry_delay_for_height(&self.board, &self.active);
+        }
+        if cleared > 0 {
+            delay += self.rules.line_clear_delay;
+        }
+        if cleared > 0 && self.cascade_enabled {
+            // The emptied rows stay on screen for a moment before the stack settles
+            self.cascade = Some(Cascade { step: 1, spawn_delay: delay });
+            self.entry_timer = Some(CASCADE_STEP_SECS);
+        } else if delay > 0.0 {
+            self.entry_timer = Some(delay);
+        } else {
+            self.spawn_next();
+        }
+    }
+
+    /// Settle the stack for the next step of a cascade in progress. Each step that clears
+    /// lines scores them times its step number and waits CASCADE_STEP_SECS (plus the
+    /// rules' line clear delay) for the next; a step without lines ends the chain and
+    /// starts the entry delay.
+    fn cascade_step(&mut self) {
+        let Some(mut cascade) = self.cascade.take() else {
+            return;
+        };
+        let line_clear = self.board.settle();
+        if line_clear.lines == 0 {
+            self.entry_timer = None;
+            if cascade.spawn_delay > 0.0 {
+                self.entry_timer = Some(cascade.spawn_delay);
+            } else {
+                self.spawn_next();
+            }
+            return;
+        }
+
+        cascade.step += 1;
+        let chain = if self.chain_enabled { self.chain.multiplier() } else { 1 };
+        let points = scoring::cascade_points(line_clear.lines, cascade.step, &self.rules.scoring);
+        self.score += points as u64 * (self.level as u64 + 1) * chain as u64;
+        let perfect_clear = self.rules.scoring.perfect_clear && self.board.is_empty();
+        if perfect_clear {
+            self.score += scoring::perfect_clear_points(line_clear.lines, false) as u64 * (self.level as u64 + 1);
+        }
+        let mut lines = vec![format!("CASCADE ×{}", cascade.step)];
+        self.fire_powers(line_clear.powers, &mut lines);
+        self.callout = Some(Callout { lines, age: 0.0, perfect_clear });
+        self.add_lines(line_clear.lines);
+        self.entry_timer = Some(CASCADE_STEP_SECS + self.rules.line_clear_delay);
+        self.cascade = Some(cascade);
+    }
+
+    /// Apply the powers of cleared cells, naming them in `lines` for the callout.
+    fn fire_powers(&mut self, powers: Vec<Power>, lines: &mut Vec<String>) {
+        for power in powers {
+            match power {
+                // The blast itself already happened on the board
+                Power::Nova => lines.push("NOVA".to_owned()),
+                Power::SlowTime => {
+                    self.slow_time = SLOW_TIME_SECS;
+                    lines.push("SLOW TIME".to_owned());
+                }
+            }
+        }
+    }
+
+    fn add_lines(&mut self, cleared: u32) {
         if cleared > 0 {
             self.lines += cleared;
-            // very simple scoring for now
-            self.score += match cleared {
-                1 => 100,
-                2 => 300,
-                3 => 500,
-                _ => 800,
-            } as u64 * (self.level as u64 + 1);
-            if self.lines / 10 > self.level {
-                self.level += 1;
-                // simple gravity ramp
-                self.gravity_interval = (self.gravity_interval * 0.9).max(1.0 / 60.0);
-            }
-        }
-        self.active = Self::spawn(&mut self.bag, &self.board);
-        // If spawn immediately collides, treat as simple "top out": reset board and continue.
+            // Gravity follows the level through the rule set's curve
+            let level = self.rules.level_progression.level(self.start_level, self.lines, self.rules.lines_per_level);
+            self.level = self.level.max(level);
+        }
+    }
+
+    /// Push `rows` garbage rows under the stack, the lowest with its hole in column `hole`.
+    /// A piece in play that the rising stack would overlap is lifted out of it, by up to
+    /// `rows` rows; one floating above stays put. The game tops out if anything is pushed
+    /// past the top of the vanish zone or the piece can't be lifted clear.
+    pub fn push_garbage(&mut self, rows: u32, hole: i16, holes: GarbageHoles) {
+        if self.is_over() {
+            return;
+        }
+        let in_play = self.entry_timer.is_none();
+        let y = self.active.y;
+        let active = in_play.then_some(&mut self.active);
+        let rise = self.board.push_garbage(rows, hole, holes, &mut self.garbage_rng, active);
+        self.lowest_y -= y - self.active.y;
+        if rise.overflow || rise.piece_blocked {
+            self.phase = Phase::GameOver(TopOut::Overflow);
+        }
+    }
+
+    /// Bring in the next piece, applying IHS and IRS from buttons pressed during the entry
+    /// delay and still held; a button the previous piece already used does not carry over.
+    /// The rotated pose is only used if it fits; otherwise the piece enters unrotated.
+    fn spawn_next(&mut self) {
+        let (now, fresh) = (self.prev_input, self.spawn_presses);
+        let held = GameInput {
+            rot_cw: now.rot_cw && fresh.rot_cw,
+            rot_ccw: now.rot_ccw && fresh.rot_ccw,
+            rot_180: now.rot_180 && fresh.rot_180,
+            hold: now.hold && fresh.hold,
+            ..GameInput::default()
+        };
+        self.spawn_presses = GameInput::default();
+        self.entry_timer = None;
+        self.hold_available = true;
+
+        let next = self.bag.next();
+        if self.rules.ihs && self.rules.hold && held.hold {
+            // The dealt piece goes straight to hold with the power it would have spawned with
+            let power = Self::roll_power(&mut self.power_rng, self.pieces.minos(next, Rot::R0).len());
+            self.swap_hold(next, power);
+            self.hold_available = false;
+        } else {
+            self.spawn_piece(next);
+        }
+
+        let initial_rot = if held.rot_ccw {
+            Some(self.active.rot.ccw())
+        } else if held.rot_cw {
+            Some(self.active.rot.cw())
+        } else if held.rot_180 && self.rules.rotate_180 {
+            Some(self.active.rot.flip())
+        } else {
+            None
+        };
+        if let Some(rot) = initial_rot.filter(|_| self.rules.irs) {
+            let np = self.active.rotated(&self.pieces, rot);
+            if self.can_place(&np) {
+                self.active = np;
+            }
+        }
+
         if self.board.collides(&self.active) {
-            self.board.clear();
-            self.score = 0;
-            self.level = 0;
-            self.lines = 0;
+            self.phase = Phase::GameOver(TopOut::BlockOut);
         }
         self.acc = 0.0;
+        self.reset_lock_state();
     }
 
     pub fn update(&mut self, dt: f32, input: GameInput) {
-        if input.restart {
-            *self = Self::new();
+        let pressed = input.pressed_since(self.prev_input);
+        self.prev_input = input;
+
+        if pressed.restart {
+            self.restart();
+            return;
+        }
+        // Simulation is frozen once topped out; only restart is accepted.
+        if self.is_over() {
             return;
         }
-        if input.pause {
+        if pressed.pause {
             self.paused = !self.paused;
         }
         if self.paused {
             return;
         }
+        self.elapsed += dt;
+        if self.chain_enabled {
+            self.chain.tick(dt);
+        }
+        self.slow_time = (self.slow_time - dt).max(0.0);
+        if let Some(callout) = &mut self.callout {
+            callout.age += dt;
+            if callout.age >= CALLOUT_SECS {
+                self.callout = None;
+            }
+        }
+
+        // Entry delay: nothing is in play, but DAS keeps charging and IRS/IHS buttons are read at spawn
+        if let Some(timer) = self.entry_timer {
+            self.spawn_presses = self.spawn_presses.merge(pressed);
+            let _ = self.shift.tick(&self.handling(), dt, input.left, input.right);
+            if timer > dt {
+                self.entry_timer = Some(timer - dt);
+            } else if self.cascade.is_some() {
+                self.cascade_step();
+            } else {
+                self.spawn_next();
+            }
+            return;
+        }
+
+        if pressed.hold && self.try_hold() && self.is_over() {
+            return;
+        }
 
         // Rotate first (prioritize CCW over CW to avoid conflicts)
-        if input.rot_ccw {
+        if pressed.rot_ccw {
             let _ = self.try_rotate(self.active.rot.ccw());
-        } else if input.rot_cw {
+        } else if pressed.rot_cw {
             let _ = self.try_rotate(self.active.rot.cw());
+        } else if pressed.rot_180 {
+            let _ = self.try_flip();
         }
 
-        // Horizontal movement
-        if input.left {
-            let _ = self.try_move(-1, 0);
-        }
-        if input.right {
-            let _ = self.try_move(1, 0);
+        // Horizontal movement (DAS/ARR)
+        let shift = self.shift.tick(&self.handling(), dt, input.left, input.right);
+        for _ in 0..shift.steps {
+            if !self.try_move(shift.dir, 0) {
+                break;
+            }
         }
 
         // Hard drop: move down until collision, then lock
-        if input.hard_drop {
-            while self.try_move(0, 1) {}
+        if pressed.hard_drop && self.rules.hard_drop {
+            let mut rows = 0;
+            while self.try_move(0, 1) {
+                rows += 1;
+            }
+            self.score += rows * self.rules.scoring.hard_drop as u64;
             self.lock_and_spawn();
             return;
         }
 
-        // Soft drop: try one row; if blocked, lock
+        // Soft drop: one row on press, then SDF times gravity speed (or the rules' fixed
+        // rate, unless gravity is faster) while held.
+        // Resting pieces are left to the lock delay, unless the rules lock them right away.
         if input.soft_drop {
-            if !self.try_move(0, 1) {
+            let sdf = self.handling().sdf;
+            let mut blocked = false;
+            if sdf >= SDF_INSTANT && self.rules.soft_drop_rate.is_none() {
+                while self.soft_drop_step() {}
+                blocked = true;
+            } else if pressed.soft_drop {
+                self.soft_acc = 0.0;
+                blocked = !self.soft_drop_step();
+            } else {
+                let interval = match self.rules.soft_drop_rate {
+                    Some(rate) => rate.min(self.gravity_interval()),
+                    None => self.gravity_interval() / sdf.max(1.0),
+                };
+                self.soft_acc += dt;
+                while self.soft_acc >= interval {
+                    self.soft_acc -= interval;
+                    if !self.soft_drop_step() {
+                        self.soft_acc = 0.0;
+                        blocked = true;
+                        break;
+                    }
+                }
+            }
+            if blocked && self.rules.gravity_lock {
                 self.lock_and_spawn();
                 return;
             }
@@ -186,35 +811,118 @@ impl Game {
 
         // Update pulse time for gravity oscillation
         self.pulse_time += dt;
-        
-        // Apply pulse gravity effect if enabled
-        if self.pulse_enabled {
-            // Sine wave oscillation with 8 second period (2π / 8 = π/4)
-            let pulse_factor = (self.pulse_time * std::f32::consts::PI / 4.0).sin() * 0.5 + 1.0; // Oscillates between 0.5 and 1.5
-            self.gravity_interval = self.base_gravity_interval * pulse_factor;
+
+        // Gravity: may cover several rows per update at high levels; 20G drops instantly
+        let interval = self.gravity_interval();
+        if interval <= gravity::TWENTY_G {
+            while self.try_move(0, 1) {}
+            self.acc = 0.0;
+            if self.rules.gravity_lock {
+                self.lock_and_spawn();
+                return;
+            }
+        } else if input.soft_drop && self.rules.soft_drop_rate.is_some() {
+            // A fixed-rate soft drop replaces gravity while held; it is never the slower one
+            self.acc = 0.0;
+        } else {
+            self.acc += dt;
+            while self.acc >= interval {
+                self.acc -= interval;
+                if !self.try_move(0, 1) {
+                    self.acc = 0.0;
+                    if self.rules.gravity_lock {
+                        self.lock_and_spawn();
+                        return;
+                    }
+                    break;
+                }
+            }
         }
 
-        // Gravity
-        self.acc += dt;
-        if self.acc >= self.gravity_interval {
-            self.acc -= self.gravity_interval;
-            if !self.try_move(0, 1) {
+        // Lock delay: only runs while the piece is resting
+        if self.grounded() && !self.rules.gravity_lock {
+            self.lock_timer += dt;
+            if self.lock_timer >= self.rules.lock_delay {
                 self.lock_and_spawn();
             }
         }
     }
 
-    pub fn active(&self) -> &ActivePiece {
-        &self.active
+    /// Level as the rules number it for the player.
+    pub fn shown_level(&self) -> u32 {
+        self.level + self.rules.first_level
+    }
+
+    /// Seed this game was started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Consecutive line clears after the first (0 when no combo is running).
+    pub fn combo(&self) -> u32 {
+        self.streaks.combo()
+    }
+
+    /// Back-to-back bonuses earned in a row.
+    pub fn back_to_back(&self) -> u32 {
+        self.streaks.back_to_back()
+    }
+
+    /// Combo chain state when the mechanic is enabled.
+    pub fn combo_chain(&self) -> Option<&ComboChain> {
+        self.chain_enabled.then_some(&self.chain)
+    }
+
+    /// Seconds of Slow Time left (0 when inactive).
+    pub fn slow_time(&self) -> f32 {
+        self.slow_time
+    }
+
+    /// Current announcement, if one is still showing.
+    pub fn callout(&self) -> Option<&Callout> {
+        self.callout.as_ref()
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.phase, Phase::GameOver(_))
+    }
+
+    /// Seconds of unpaused play so far (frozen at game over).
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// The piece in play, or None during entry delay.
+    pub fn active(&self) -> Option<&ActivePiece> {
+        self.entry_timer.is_none().then_some(&self.active)
     }
 
     pub fn board(&self) -> &Board {
         &self.board
     }
 
+    /// The pieces this game deals from.
+    pub fn pieces(&self) -> &PieceSet {
+        &self.pieces
+    }
+
+    /// Piece currently in the hold slot, if any.
+    pub fn held(&self) -> Option<Piece> {
+        self.hold
+    }
+
+    /// Whether a hold swap is allowed for the current piece.
+    pub fn hold_available(&self) -> bool {
+        self.hold_available
+    }
+
     /// Calculate where the current piece would land (for ghost piece display)
-    pub fn ghost_position(&self) -> ActivePiece {
-        let mut ghost = self.active;
+    pub fn ghost_position(&self) -> Option<ActivePiece> {
+        let mut ghost = *self.active()?;
         // Drop the ghost piece until it would collide
         while !self.board.collides(&{
             let mut test_piece = ghost;
@@ -223,11 +931,873 @@ impl Game {
         }) {
             ghost.y += 1;
         }
-        ghost
+        Some(ghost)
     }
 
     /// Get the next N pieces for preview
-    pub fn preview_pieces(&mut self, count: usize) -> Vec<Tetromino> {
+    pub fn preview_pieces(&self, count: usize) -> Vec<Piece> {
         self.bag.peek(count)
     }
 }
+
+/// NES entry delay on top of the base: 2 frames for every 4 rows the piece's lowest mino
+/// locked above the bottom two, capped at 8 frames.
+fn entry_delay_for_height(board: &Board, piece: &ActivePiece) -> f32 {
+    let bottom = piece.cells().iter().map(|&(_, y)| y).max().unwrap_or(board.h - 1);
+    let rows_up = (board.h - 1 - bottom).max(0) as u32;
+    ((rows_up + 2) / 4 * 2).min(8) as f32 * timestep::TICK_DT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::{Cell, CellKind};
+
+    fn filled(board: &mut Board, x: i16, y: i16) {
+        board.set(x, y, Some(Cell { kind: CellKind::Piece(Piece::O), power: None }));
+    }
+
+    fn new_game() -> Game {
+        Game::new(Box::new(random::Bag::new(1, 7, None)))
+    }
+
+    fn game_with(board: Board, piece: ActivePiece) -> Game {
+        let mut game = new_game();
+        game.board = board;
+        game.active = piece;
+        game.power_rng = Pcg32::new(0, POWER_STREAM);
+        game.reset_lock_state();
+        game
+    }
+
+    fn shift(left: bool) -> GameInput {
+        GameInput { left, right: !left, ..Default::default() }
+    }
+
+    fn locked(game: &Game, p: &ActivePiece) -> bool {
+        p.cells().iter().all(|&(x, y)| game.board.get(x, y).is_some())
+    }
+
+    fn piece(t: Piece, rot: Rot, x: i16, y: i16) -> ActivePiece {
+        let set = PieceSet::standard(RotationSystem::Srs);
+        ActivePiece::new(&set, t, x, y).rotated(&set, rot)
+    }
+
+    #[test]
+    fn test_rotation_without_obstruction_uses_first_test() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 10));
+        assert!(game.try_rotate(Rot::R90));
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R90, 4, 10));
+    }
+
+    #[test]
+    fn test_t_kicks_off_right_wall() {
+        // Vertical T hugging the right wall; flat pose would stick out, so test 2 shifts it left.
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R270, 9, 10));
+        assert!(game.try_rotate(Rot::R0));
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R0, 8, 10));
+    }
+
+    #[test]
+    fn test_kick_tests_are_tried_in_order() {
+        // Block test 2 of L->0 so test 3 (-1, -1 in guideline coordinates) is used.
+        let mut board = Board::new();
+        filled(&mut board, 7, 10);
+        let mut game = game_with(board, piece(Piece::T, Rot::R270, 9, 10));
+        assert!(game.try_rotate(Rot::R0));
+        assert_eq!((game.active.x, game.active.y), (8, 11));
+    }
+
+    #[test]
+    fn test_i_floor_kick_uses_last_test() {
+        // Flat I on the floor: only the fifth 0->R test (+1, +2) lifts it clear.
+        let floor = BOARD_H - 1;
+        let mut game = game_with(Board::new(), piece(Piece::I, Rot::R0, 4, floor));
+        assert!(game.try_rotate(Rot::R90));
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R90, 5, floor - 2));
+        assert!(game.active.cells().iter().all(|&(x, y)| x == 6 && y <= floor));
+    }
+
+    #[test]
+    fn test_i_kicks_off_left_wall() {
+        // Vertical I in column 0 rotating CCW to flat: the first two tests poke out, +1 fits.
+        let mut game = game_with(Board::new(), piece(Piece::I, Rot::R270, 0, 10));
+        assert!(game.try_rotate(Rot::R180));
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R180, 1, 10));
+    }
+
+    #[test]
+    fn test_rotation_fails_when_every_kick_collides() {
+        // Vertical I in a one-wide well: no horizontal pose fits anywhere.
+        let mut board = Board::new();
+        for y in 0..BOARD_H {
+            for x in 0..BOARD_W {
+                if x != 5 {
+                    filled(&mut board, x, y);
+                }
+            }
+        }
+        let start = piece(Piece::I, Rot::R90, 4, 10);
+        let mut game = game_with(board, start);
+        assert!(!game.try_rotate(Rot::R180));
+        assert!(!game.try_rotate(Rot::R0));
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (start.rot, start.x, start.y));
+    }
+
+    #[test]
+    fn test_spawn_at_guideline_columns_and_rows() {
+        let game = new_game();
+        let t = game.spawn(Piece::T);
+        assert_eq!((t.x, t.y), (4, 0));
+        let mut t_cells = t.cells().to_vec();
+        t_cells.sort();
+        assert_eq!(t_cells, [(3, 0), (4, -1), (4, 0), (5, 0)]);
+
+        let mut i_cols: Vec<i16> = game.spawn(Piece::I).cells().iter().map(|c| c.0).collect();
+        i_cols.sort();
+        assert_eq!(i_cols, [3, 4, 5, 6]);
+        let mut o_cells = game.spawn(Piece::O).cells().to_vec();
+        o_cells.sort();
+        assert_eq!(o_cells, [(4, -1), (4, 0), (5, -1), (5, 0)]);
+    }
+
+    #[test]
+    fn test_spawn_stays_in_vanish_zone_when_blocked_below() {
+        let mut game = new_game();
+        filled(&mut game.board, 4, 0);
+        let t = game.spawn(Piece::T);
+        assert_eq!((t.x, t.y), (4, -1));
+    }
+
+    #[test]
+    fn test_rotation_at_spawn_uses_vanish_zone() {
+        let spawned = new_game().spawn(Piece::I);
+        let mut game = game_with(Board::new(), spawned);
+        assert!(game.try_rotate(Rot::R270));
+        assert_eq!(game.active.y, 0);
+        assert!(game.active.cells().iter().any(|&(_, y)| y < 0));
+    }
+
+    #[test]
+    fn test_first_hold_stores_piece_and_draws_from_bag() {
+        let mut game = new_game();
+        let current = game.active.t;
+        let upcoming = game.preview_pieces(1)[0];
+        assert!(game.try_hold());
+        assert_eq!(game.held(), Some(current));
+        assert_eq!(game.active.t, upcoming);
+        assert!(!game.hold_available());
+    }
+
+    #[test]
+    fn test_hold_locked_out_until_next_piece() {
+        let mut game = new_game();
+        let first = game.active.t;
+        assert!(game.try_hold());
+        let second = game.active.t;
+        assert!(!game.try_hold());
+        assert_eq!((game.held(), game.active.t), (Some(first), second));
+
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert!(game.hold_available());
+        let third = game.active.t;
+        assert!(game.try_hold());
+        assert_eq!((game.held(), game.active.t), (Some(third), first));
+    }
+
+    #[test]
+    fn test_held_piece_keeps_its_power() {
+        let mut game = new_game();
+        let first = game.active.t;
+        game.active.power = Some((1, Power::Nova));
+        assert!(game.try_hold());
+        game.active.power = None;
+        game.hold_available = true;
+        assert!(game.try_hold());
+        assert_eq!((game.active.t, game.active.power), (first, Some((1, Power::Nova))));
+        // The piece that went in without a power comes back without one
+        game.hold_available = true;
+        assert!(game.try_hold());
+        assert_eq!(game.active.power, None);
+    }
+
+    #[test]
+    fn test_hold_disabled_by_rules() {
+        let mut game = new_game();
+        game.rules.hold = false;
+        let current = game.active.t;
+        game.update(0.0, GameInput { hold: true, ..Default::default() });
+        assert_eq!((game.held(), game.active.t), (None, current));
+    }
+
+    #[test]
+    fn test_block_out_ends_game_and_keeps_stats() {
+        // Stack poking into the vanish zone right where the next piece spawns
+        let mut board = Board::new();
+        for x in 3..=6 {
+            filled(&mut board, x, -1);
+            filled(&mut board, x, 0);
+        }
+        let mut game = game_with(board, piece(Piece::O, Rot::R0, 0, 5));
+        game.score = 1234;
+        game.lines = 7;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.phase(), Phase::GameOver(TopOut::BlockOut));
+        // Only the 14-row hard drop scored
+        assert_eq!((game.score, game.lines), (1234 + 28, 7));
+
+        // Frozen: further input changes nothing
+        let before = game.active;
+        game.update(1.0, GameInput { left: true, hard_drop: true, ..Default::default() });
+        assert_eq!((game.active.x, game.active.y), (before.x, before.y));
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn test_lock_out_when_piece_locks_above_skyline() {
+        let mut board = Board::new();
+        for y in 0..BOARD_H {
+            filled(&mut board, 0, y);
+        }
+        let mut game = game_with(board, piece(Piece::I, Rot::R90, -1, -4));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.phase(), Phase::GameOver(TopOut::LockOut));
+    }
+
+    #[test]
+    fn test_restart_clears_game_over() {
+        let mut game = new_game();
+        game.phase = Phase::GameOver(TopOut::BlockOut);
+        game.update(0.0, GameInput { restart: true, ..Default::default() });
+        assert_eq!(game.phase(), Phase::Playing);
+    }
+
+    #[test]
+    fn test_grounded_piece_waits_for_lock_delay() {
+        let start = piece(Piece::T, Rot::R0, 4, BOARD_H - 1);
+        let mut game = game_with(Board::new(), start);
+        game.update(0.25, GameInput::default());
+        game.update(0.125, GameInput::default());
+        assert!(!locked(&game, &start));
+        game.update(0.125, GameInput::default());
+        assert!(locked(&game, &start));
+    }
+
+    #[test]
+    fn test_lock_delay_is_configurable() {
+        let start = piece(Piece::T, Rot::R0, 4, BOARD_H - 1);
+        let mut game = game_with(Board::new(), start);
+        game.rules.lock_delay = 1.0;
+        game.update(0.75, GameInput::default());
+        assert!(!locked(&game, &start));
+        game.update(0.25, GameInput::default());
+        assert!(locked(&game, &start));
+    }
+
+    #[test]
+    fn test_soft_drop_on_stack_does_not_lock_instantly() {
+        let start = piece(Piece::T, Rot::R0, 4, BOARD_H - 1);
+        let mut game = game_with(Board::new(), start);
+        game.update(0.0, GameInput { soft_drop: true, ..Default::default() });
+        assert!(!locked(&game, &start));
+        assert_eq!((game.active.x, game.active.y), (4, BOARD_H - 1));
+    }
+
+    #[test]
+    fn test_move_resets_lock_timer() {
+        let start = piece(Piece::T, Rot::R0, 4, BOARD_H - 1);
+        let mut game = game_with(Board::new(), start);
+        game.update(0.375, GameInput::default());
+        game.update(0.25, GameInput { left: true, ..Default::default() });
+        game.update(0.125, GameInput::default());
+        assert_eq!(game.active.x, 3);
+        assert!(!locked(&game, &{ game.active }));
+        game.update(0.125, GameInput::default());
+        assert!(locked(&game, &piece(Piece::T, Rot::R0, 3, BOARD_H - 1)));
+    }
+
+    #[test]
+    fn test_resets_stop_after_limit() {
+        let start = piece(Piece::T, Rot::R0, 4, BOARD_H - 1);
+        let mut game = game_with(Board::new(), start);
+        for i in 0..MAX_LOCK_RESETS {
+            game.update(0.125, shift(i & 1 == 0));
+        }
+        assert_eq!(game.lock_resets, MAX_LOCK_RESETS);
+        // Budget spent: the timer keeps running through further moves
+        game.update(0.125, shift(false));
+        game.update(0.125, shift(true));
+        assert!(game.board.get(game.active.x, BOARD_H - 1).is_none());
+        let resting = game.active;
+        game.update(0.125, GameInput::default());
+        assert!(locked(&game, &resting));
+    }
+
+    #[test]
+    fn test_new_lowest_row_restores_reset_budget() {
+        // Platform under columns 0..=5; T rests on it one row above the floor
+        let mut board = Board::new();
+        for x in 0..=5 {
+            filled(&mut board, x, BOARD_H - 1);
+        }
+        let mut game = game_with(board, piece(Piece::T, Rot::R0, 4, BOARD_H - 2));
+        for i in 0..MAX_LOCK_RESETS {
+            game.update(0.0, shift(i & 1 == 0));
+        }
+        assert_eq!(game.lock_resets, MAX_LOCK_RESETS);
+
+        // Slide off the platform edge and drop to the floor
+        for _ in 0..4 {
+            game.update(0.0, GameInput { right: true, ..Default::default() });
+            game.update(0.0, GameInput::default());
+        }
+        assert_eq!(game.active.x, 7);
+        game.update(0.0, GameInput { soft_drop: true, ..Default::default() });
+        assert_eq!((game.active.y, game.lowest_y, game.lock_resets), (BOARD_H - 1, BOARD_H - 1, 0));
+
+        game.update(0.375, GameInput::default());
+        game.update(0.25, GameInput { right: true, ..Default::default() });
+        assert!(!locked(&game, &{ game.active }));
+    }
+
+    #[test]
+    fn test_hard_drop_locks_immediately() {
+        let start = piece(Piece::T, Rot::R0, 4, 5);
+        let mut game = game_with(Board::new(), start);
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert!(locked(&game, &piece(Piece::T, Rot::R0, 4, BOARD_H - 1)));
+    }
+
+    #[test]
+    fn test_held_direction_auto_repeats_after_das() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.handling = Handling { das: 0.125, arr: 0.0625, sdf: 20.0 };
+        let left = GameInput { left: true, ..Default::default() };
+        game.update(0.0, left);
+        assert_eq!(game.active.x, 3);
+        game.update(0.0625, left);
+        assert_eq!(game.active.x, 3);
+        game.update(0.0625, left);
+        assert_eq!(game.active.x, 2);
+        game.update(0.125, left);
+        assert_eq!(game.active.x, 1); // stopped by the wall
+    }
+
+    #[test]
+    fn test_zero_arr_slides_to_wall_once_charged() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.handling = Handling { das: 0.125, arr: 0.0, sdf: 20.0 };
+        let right = GameInput { right: true, ..Default::default() };
+        game.update(0.0, right);
+        game.update(0.125, right);
+        assert_eq!(game.active.x, BOARD_W - 2);
+    }
+
+    #[test]
+    fn test_soft_drop_factor_scales_gravity() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.handling.sdf = 20.0; // 1s level-1 gravity -> 0.05s per row
+        let down = GameInput { soft_drop: true, ..Default::default() };
+        game.update(0.0, down);
+        assert_eq!(game.active.y, 6);
+        game.update(0.125, down);
+        assert_eq!(game.active.y, 8);
+
+        game.handling.sdf = SDF_INSTANT;
+        game.update(0.0, down);
+        assert_eq!(game.active.y, BOARD_H - 1);
+    }
+
+    #[test]
+    fn test_gravity_follows_level_curve() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 0));
+        game.level = 4;
+        assert_eq!(game.gravity_interval(), gravity::seconds_per_row(4));
+        game.update(gravity::seconds_per_row(4) * 2.5, GameInput::default());
+        assert_eq!(game.active.y, 2);
+    }
+
+    #[test]
+    fn test_disabling_pulse_restores_level_speed() {
+        let mut game = new_game();
+        game.level = 6;
+        game.set_pulse_gravity(true);
+        game.pulse_time = 2.0;
+        assert!(game.gravity_interval() > gravity::seconds_per_row(6));
+        game.set_pulse_gravity(false);
+        assert_eq!(game.gravity_interval(), gravity::seconds_per_row(6));
+    }
+
+    #[test]
+    fn test_20g_drops_to_stack_immediately() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 0));
+        game.level = 25;
+        game.update(0.0, GameInput::default());
+        assert_eq!(game.active.y, BOARD_H - 1);
+    }
+
+    #[test]
+    fn test_level_advances_every_ten_lines() {
+        let mut board = Board::new();
+        for x in 0..BOARD_W {
+            if !(4..=7).contains(&x) {
+                filled(&mut board, x, BOARD_H - 1);
+            }
+        }
+        let mut game = game_with(board, piece(Piece::I, Rot::R0, 5, 0));
+        game.lines = 9;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!((game.lines, game.level), (10, 1));
+    }
+
+    #[test]
+    fn test_t_spin_double_scores_and_calls_out() {
+        // Vertical T above a TSD slot with an overhang; rotating CW kicks it into the slot
+        let mut board = Board::new();
+        let rows = ["##........", "#...######", "##.#######"];
+        for (i, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    filled(&mut board, x as i16, BOARD_H - 3 + i as i16);
+                }
+            }
+        }
+        let mut game = game_with(board, piece(Piece::T, Rot::R90, 1, BOARD_H - 3));
+        game.update(0.0, GameInput { rot_cw: true, ..Default::default() });
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R180, 2, BOARD_H - 2));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!((game.lines, game.score), (2, 1200));
+        assert_eq!(game.callout().unwrap().lines, ["T-SPIN DOUBLE"]);
+    }
+
+    #[test]
+    fn test_zero_line_t_spin_scores() {
+        let mut board = Board::new();
+        filled(&mut board, 0, BOARD_H - 3);
+        filled(&mut board, 0, BOARD_H - 1);
+        filled(&mut board, 2, BOARD_H - 1);
+        let mut game = game_with(board, piece(Piece::T, Rot::R90, 1, BOARD_H - 2));
+        // CW to R180 in place: nub points down between the two floor blocks
+        game.update(0.0, GameInput { rot_cw: true, ..Default::default() });
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!((game.lines, game.score), (0, 400));
+    }
+
+    #[test]
+    fn test_shift_after_rotation_cancels_t_spin() {
+        let mut board = Board::new();
+        filled(&mut board, 0, BOARD_H - 3);
+        filled(&mut board, 0, BOARD_H - 1);
+        filled(&mut board, 2, BOARD_H - 1);
+        let mut game = game_with(board, piece(Piece::T, Rot::R0, 1, 10));
+        game.update(0.0, GameInput { rot_cw: true, ..Default::default() });
+        game.update(0.0, GameInput { rot_cw: false, ..Default::default() });
+        game.update(0.0, GameInput { rot_cw: true, ..Default::default() });
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        // Six hard-dropped rows, no T-spin
+        assert_eq!(game.score, 12);
+    }
+
+    /// Rows `BOARD_H - n..BOARD_H` filled except for a well in the rightmost column.
+    fn well_board(n: i16) -> Board {
+        let mut board = Board::new();
+        for y in BOARD_H - n..BOARD_H {
+            for x in 0..BOARD_W - 1 {
+                filled(&mut board, x, y);
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn test_back_to_back_tetrises_with_combo() {
+        let mut game = game_with(well_board(8), piece(Piece::I, Rot::R90, BOARD_W - 2, 0));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        // Tetris plus 17 hard-dropped rows
+        assert_eq!((game.lines, game.score, game.combo(), game.back_to_back()), (4, 800 + 34, 0, 0));
+
+        game.active = piece(Piece::I, Rot::R90, BOARD_W - 2, 0);
+        game.update(0.0, GameInput::default());
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        // 800 * 1.5 back-to-back + 50 * 1 combo; the second tetris also empties the board
+        assert_eq!((game.lines, game.score, game.combo(), game.back_to_back()), (8, 834 + 1250 + 34 + 3200, 1, 1));
+        assert_eq!(game.callout().unwrap().lines, ["TETRIS", "BACK-TO-BACK", "COMBO ×1"]);
+        assert!(game.callout().unwrap().perfect_clear);
+    }
+
+    #[test]
+    fn test_classic_rules_score_tetris_and_wait_for_line_clear() {
+        // Resting at the bottom of the well; there is no hard drop, gravity locks it
+        let mut game = game_with(well_board(4), piece(Piece::I, Rot::R90, BOARD_W - 2, BOARD_H - 3));
+        game.rules = RuleSet::classic();
+        game.update(gravity::NES_FRAMES[0] as f32 * timestep::TICK_DT, GameInput::default());
+        assert_eq!((game.lines, game.score, game.level), (4, 1200, 0));
+        assert!(game.active().is_none());
+
+        let wait = game.rules.entry_delay + game.rules.line_clear_delay;
+        game.update(wait - 0.01, GameInput::default());
+        assert!(game.active().is_none());
+        game.update(0.02, GameInput::default());
+        assert!(game.active().is_some());
+    }
+
+    #[test]
+    fn test_nes_entry_delay_grows_with_lock_height() {
+        let board = Board::new();
+        let at = |y| piece(Piece::O, Rot::R0, 4, y);
+        // The O's bottom cells sit on row y
+        assert_eq!(entry_delay_for_height(&board, &at(BOARD_H - 2)), 0.0);
+        assert_eq!(entry_delay_for_height(&board, &at(BOARD_H - 3)), 2.0 * timestep::TICK_DT);
+        assert_eq!(entry_delay_for_height(&board, &at(BOARD_H - 7)), 4.0 * timestep::TICK_DT);
+        assert_eq!(entry_delay_for_height(&board, &at(0)), 8.0 * timestep::TICK_DT);
+    }
+
+    #[test]
+    fn test_nes_piece_locks_when_gravity_fails() {
+        let mut game = new_game().with_rules(RuleSet::classic());
+        game.active = game.spawn(Piece::T);
+        // Columns 4 to 6 as on the NES, flat side up
+        assert_eq!(*game.active.cells(), [(4, 0), (5, 0), (6, 0), (5, 1)]);
+        game.active.y = BOARD_H - 2;
+        game.reset_lock_state();
+        // Resting on the floor: no lock delay, the next failed gravity step locks it
+        let frames = gravity::NES_FRAMES[0] as f32 * timestep::TICK_DT;
+        game.update(frames - 0.01, GameInput::default());
+        assert!(game.board.is_empty());
+        game.update(0.02, GameInput::default());
+        assert!(!game.board.is_empty());
+    }
+
+    #[test]
+    fn test_nes_soft_drop_is_one_row_every_other_frame() {
+        let frame = timestep::TICK_DT;
+        for level in [0, 18] {
+            let mut game = new_game().with_rules(RuleSet::classic()).with_start_level(level);
+            game.active = game.spawn(Piece::T);
+            let y = game.active.y;
+            let soft = GameInput { soft_drop: true, ..Default::default() };
+            // One row on the press, then one per two frames whatever the gravity
+            for _ in 0..11 {
+                game.update(frame, soft);
+            }
+            assert_eq!(game.active.y - y, 6, "level {level}");
+        }
+    }
+
+    #[test]
+    fn test_classic_has_no_hard_drop() {
+        let mut game = new_game().with_rules(RuleSet::classic());
+        game.active = game.spawn(Piece::T);
+        let y = game.active.y;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.active.y, y);
+        assert!(game.board.is_empty());
+    }
+
+    #[test]
+    fn test_garbage_lifts_piece_in_play_and_tops_out() {
+        let mut game = game_with(Board::new(), piece(Piece::O, Rot::R0, 4, BOARD_H - 1));
+        game.push_garbage(2, 0, GarbageHoles::Clean);
+        assert_eq!((game.active.y, game.phase()), (BOARD_H - 3, Phase::Playing));
+        assert_eq!(game.board.get(1, BOARD_H - 1).unwrap().kind, CellKind::Garbage);
+
+        // Enough rows to push the stack through the top of the vanish zone
+        let rows = (BOARD_H + BOARD_HIDDEN_H) as u32;
+        game.push_garbage(rows, 0, GarbageHoles::Clean);
+        assert_eq!(game.phase(), Phase::GameOver(TopOut::Overflow));
+    }
+
+    #[test]
+    fn test_dig_rules_start_on_garbage() {
+        let game = new_game().with_rules(RuleSet::dig());
+        let garbage = |y| (0..BOARD_W).filter(|&x| game.board.get(x, y).is_some_and(|c| c.kind == CellKind::Garbage)).count();
+        assert!((BOARD_H - 10..BOARD_H).all(|y| garbage(y) == BOARD_W as usize - 1));
+        assert_eq!(garbage(BOARD_H - 11), 0);
+        assert!(!game.board.collides(&game.active));
+    }
+
+    #[test]
+    fn test_start_level_survives_restart() {
+        let mut game = new_game().with_start_level(12);
+        game.update(0.0, GameInput { restart: true, ..Default::default() });
+        assert_eq!(game.level, 12);
+        assert_eq!(game.shown_level(), 13);
+        // Classic numbers levels from 0 like the NES
+        assert_eq!(game.with_rules(RuleSet::classic()).shown_level(), 12);
+    }
+
+    #[test]
+    fn test_custom_piece_set_deals_and_locks_its_own_shapes() {
+        let set = PieceSet::parse(
+            r#"{ "name": "Pentas", "pieces": [{ "letter": "P", "rotations": [[[0, -1], [1, -1], [0, 0], [1, 0], [0, 1]]] }] }"#,
+            false,
+        )
+        .unwrap();
+        let mut game = Game::new(Box::new(random::Bag::new(1, set.len(), None))).with_pieces(set);
+        assert_eq!(game.active.minos.len(), 5);
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        let filled = (0..BOARD_W).flat_map(|x| (0..BOARD_H).map(move |y| (x, y)));
+        assert_eq!(filled.filter(|&(x, y)| game.board.get(x, y).is_some()).count(), 5);
+
+        // A restart keeps dealing from the custom set
+        game.update(0.0, GameInput { restart: true, ..Default::default() });
+        assert_eq!(game.pieces().name, "Pentas");
+    }
+
+    #[test]
+    fn test_cascade_chain_scores_each_step() {
+        let mut board = Board::new();
+        let (floor, above) = (BOARD_H - 1, BOARD_H - 2);
+        for x in (0..BOARD_W).filter(|&x| x != 5) {
+            filled(&mut board, x, floor);
+        }
+        for x in 2..BOARD_W {
+            filled(&mut board, x, above);
+        }
+        // Left hanging over the floor's hole once the row above it clears
+        filled(&mut board, 5, above - 1);
+        let mut game = game_with(board, piece(Piece::O, Rot::R0, 0, above));
+        game.set_cascade(true);
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        // The lock's single clears first; the rest of the stack still hangs above the gap
+        assert_eq!((game.lines, game.score), (1, 100));
+        assert!(game.active().is_none() && game.board.get(5, above - 1).is_some());
+
+        // One step later the stack settles and the second single clears, worth double
+        game.update(CASCADE_STEP_SECS, GameInput::default());
+        assert_eq!((game.lines, game.score), (2, 100 + 2 * 100));
+        assert_eq!(game.callout().unwrap().lines, ["CASCADE ×2"]);
+        assert!(game.active().is_none());
+
+        // Then what is left of the O settles on the floor, ending the chain
+        game.update(CASCADE_STEP_SECS, GameInput::default());
+        assert!(game.active().is_some());
+        assert_eq!(game.lines, 2);
+        assert!(game.board.get(0, floor).is_some() && game.board.get(0, above).is_none());
+    }
+
+    #[test]
+    fn test_combo_breaks_when_a_piece_clears_nothing() {
+        let mut game = game_with(well_board(2), piece(Piece::I, Rot::R90, BOARD_W - 2, 0));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.score, 300 + 34);
+        game.active = piece(Piece::O, Rot::R0, 0, 0);
+        game.update(0.0, GameInput::default());
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        // No combo bonus, only the 19-row hard drop
+        assert_eq!((game.combo(), game.score), (0, 334 + 38));
+    }
+
+    #[test]
+    fn test_combo_chain_multiplies_clears_and_pauses_with_game() {
+        let mut game = new_game();
+        game.set_combo_chain(true);
+        // Each drop is a vertical I into a fresh one-row well: a single
+        let drop_i = |game: &mut Game| {
+            game.board = well_board(1);
+            game.active = piece(Piece::I, Rot::R90, BOARD_W - 2, 0);
+            game.update(0.0, GameInput::default());
+            game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        };
+        drop_i(&mut game);
+        assert_eq!(game.combo_chain().unwrap().multiplier(), 1);
+
+        // Pausing freezes the window
+        game.update(0.0, GameInput { pause: true, ..Default::default() });
+        game.update(10.0, GameInput::default());
+        assert!(game.paused);
+        game.update(0.0, GameInput { pause: true, ..Default::default() });
+        assert_eq!(game.combo_chain().unwrap().remaining(), 1.0);
+
+        let before = game.score;
+        drop_i(&mut game);
+        assert_eq!(game.combo_chain().unwrap().multiplier(), 2);
+        // (100 single + 50 combo) x2 chain, plus 17 hard-dropped rows (not multiplied)
+        assert_eq!(game.score - before, 300 + 34);
+    }
+
+    #[test]
+    fn test_o_piece_never_kicks() {
+        let mut game = game_with(Board::new(), piece(Piece::O, Rot::R0, 8, 10));
+        assert!(game.try_rotate(Rot::R90));
+        assert_eq!((game.active.x, game.active.y), (8, 10));
+    }
+
+    #[test]
+    fn test_clearing_slow_time_cell_halves_gravity_for_a_while() {
+        let mut i = piece(Piece::I, Rot::R90, BOARD_W - 2, 0);
+        i.power = Some((3, Power::SlowTime));
+        let mut game = game_with(well_board(1), i);
+        let normal = game.gravity_interval();
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.lines, 1);
+        assert_eq!(game.slow_time(), SLOW_TIME_SECS);
+        assert_eq!(game.gravity_interval(), normal * 2.0);
+
+        game.update(SLOW_TIME_SECS, GameInput::default());
+        assert_eq!(game.slow_time(), 0.0);
+        assert_eq!(game.gravity_interval(), normal);
+    }
+
+    #[test]
+    fn test_hard_drop_scores_two_per_row() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.score, 2 * (BOARD_H - 1 - 5) as u64);
+    }
+
+    #[test]
+    fn test_soft_drop_scores_one_per_row_but_gravity_does_not() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.update(1.0, GameInput::default());
+        assert_eq!((game.active.y, game.score), (6, 0));
+
+        let down = GameInput { soft_drop: true, ..Default::default() };
+        game.update(0.0, down);
+        game.update(0.1, down);
+        assert_eq!((game.active.y, game.score), (9, 3));
+
+        game.handling.sdf = SDF_INSTANT;
+        game.update(0.0, down);
+        assert_eq!((game.active.y, game.score), (BOARD_H - 1, 3 + (BOARD_H - 1 - 9) as u64));
+    }
+
+    #[test]
+    fn test_soft_drop_while_resting_scores_nothing() {
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, BOARD_H - 1));
+        let down = GameInput { soft_drop: true, ..Default::default() };
+        game.update(0.0, down);
+        game.update(0.25, down);
+        assert_eq!(game.score, 0);
+    }
+
+    #[test]
+    fn test_perfect_clear_single_scores_bonus_and_calls_out() {
+        let mut board = Board::new();
+        for x in 0..BOARD_W - 4 {
+            filled(&mut board, x, BOARD_H - 1);
+        }
+        let mut game = game_with(board, piece(Piece::I, Rot::R0, BOARD_W - 3, BOARD_H - 1));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert!(game.board.is_empty());
+        // 100 single + 800 perfect clear
+        assert_eq!((game.lines, game.score), (1, 900));
+        let callout = game.callout().unwrap();
+        assert!(callout.perfect_clear);
+        assert!(callout.lines.is_empty());
+    }
+
+    #[test]
+    fn test_flip_in_open_space_stays_in_place() {
+        let mut game = game_with(Board::new(), piece(Piece::J, Rot::R90, 4, 10));
+        game.update(0.0, GameInput { rot_180: true, ..Default::default() });
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R270, 4, 10));
+    }
+
+    #[test]
+    fn test_flip_on_floor_kicks_up_unless_disabled() {
+        // Flat T on the floor: pointing the nub down needs the second 0->2 test (one row up)
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, BOARD_H - 1));
+        assert!(game.try_flip());
+        assert_eq!((game.active.rot, game.active.x, game.active.y), (Rot::R180, 4, BOARD_H - 2));
+
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, BOARD_H - 1));
+        game.rules.flip_kicks = false;
+        assert!(!game.try_flip());
+        assert_eq!(game.active.rot, Rot::R0);
+    }
+
+    #[test]
+    fn test_irs_only_applies_presses_made_after_the_lock() {
+        // Without entry delay a rotate key still held from the last piece does not carry over
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        let rotate = GameInput { rot_cw: true, ..Default::default() };
+        game.update(0.0, rotate);
+        assert_eq!(game.active.rot, Rot::R90);
+        game.update(0.0, GameInput { hard_drop: true, ..rotate });
+        assert_eq!(game.active().unwrap().rot, Rot::R0);
+
+        // With one, the key has to be pressed again during the delay
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.rules.entry_delay = 0.1;
+        game.update(0.0, rotate);
+        game.update(0.0, GameInput { hard_drop: true, ..rotate });
+        game.update(0.1, rotate);
+        assert_eq!(game.active().unwrap().rot, Rot::R0);
+
+        let mut game = game_with(Board::new(), piece(Piece::T, Rot::R0, 4, 5));
+        game.rules.entry_delay = 0.1;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        game.update(0.05, rotate);
+        game.update(0.05, rotate);
+        assert_eq!(game.active().unwrap().rot, Rot::R90);
+
+        game.rules.irs = false;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        game.update(0.05, GameInput::default());
+        game.update(0.05, rotate);
+        assert_eq!(game.active().unwrap().rot, Rot::R0);
+    }
+
+    #[test]
+    fn test_inputs_held_through_entry_delay_apply_at_spawn() {
+        let mut game = game_with(Board::new(), piece(Piece::O, Rot::R0, 0, 5));
+        game.rules.entry_delay = 0.1;
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert!(game.active().is_none());
+
+        // Rotate and hold are pressed during the delay: no edges reach the next piece,
+        // so only IRS/IHS can apply them
+        let next = game.preview_pieces(2);
+        let held = GameInput { rot_ccw: true, hold: true, ..Default::default() };
+        game.update(0.05, held);
+        assert!(game.active().is_none());
+        game.update(0.05, held);
+        let active = *game.active().unwrap();
+        assert_eq!((game.held(), active.t, active.rot), (Some(next[0]), next[1], Rot::R270));
+        assert!(!game.hold_available());
+    }
+
+    #[test]
+    fn test_same_seed_replays_pieces_and_powers() {
+        let deal = |game: &mut Game| -> Vec<_> {
+            (0..40)
+                .map(|_| {
+                    let p = game.active;
+                    game.spawn_next();
+                    (p.t, p.power)
+                })
+                .collect()
+        };
+        let mut a = Game::with_seed(Box::new(random::Bag::new(1, 7, None)), 42);
+        let mut b = Game::with_seed(Box::new(random::Bag::new(1, 7, None)), 42);
+        assert_eq!(a.seed(), 42);
+        assert_eq!(deal(&mut a), deal(&mut b));
+    }
+
+    #[test]
+    fn test_restart_keeps_seed_on_request() {
+        let mut game = Game::with_seed(Box::new(random::Bag::new(1, 7, None)), 7);
+        let first = (game.active.t, game.preview_pieces(5));
+        game.reuse_seed = true;
+        game.update(0.0, GameInput { restart: true, ..Default::default() });
+        assert_eq!((game.seed(), (game.active.t, game.preview_pieces(5))), (7, first));
+
+        game.reuse_seed = false;
+        game.update(0.0, GameInput::default());
+        game.update(0.0, GameInput { restart: true, ..Default::default() });
+        assert_ne!(game.seed(), 7);
+    }
+
+    #[test]
+    fn test_four_wide_board_spawns_and_clears() {
+        let mut game = new_game().with_board_size(4, 10);
+        assert_eq!((game.board.w, game.board.h), (4, 10));
+        game.active = piece(Piece::I, Rot::R0, 1, 0);
+        assert!(game.can_place(&game.active));
+        game.update(0.0, GameInput { hard_drop: true, ..Default::default() });
+        assert_eq!(game.lines, 1);
+        assert!(game.can_place(&game.active));
+        assert!(game.active.cells().iter().all(|&(x, _)| (0..4).contains(&x)));
+    }
+}
@@ -1,14 +1,36 @@
+use std::ops::Deref;
+
+use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Tetromino {
-    I,
-    O,
-    T,
-    S,
-    Z,
-    J,
-    L,
+use super::board::Power;
+use super::kicks::{self, KickTable};
+use super::preset::Preset;
+
+/// Most minos a piece may have; enough for pentominoes and anything fitting a 3x3 box.
+pub const MAX_MINOS: usize = 9;
+
+/// A piece, as an index into its `PieceSet`. The standard set holds the seven tetrominoes
+/// in the order of the constants below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Piece(pub u8);
+
+impl Piece {
+    pub const I: Piece = Piece(0);
+    pub const O: Piece = Piece(1);
+    pub const T: Piece = Piece(2);
+    pub const S: Piece = Piece(3);
+    pub const Z: Piece = Piece(4);
+    pub const J: Piece = Piece(5);
+    pub const L: Piece = Piece(6);
+
+    /// The standard set's pieces.
+    pub const TETROMINOES: [Piece; 7] = [Piece::I, Piece::O, Piece::T, Piece::S, Piece::Z, Piece::J, Piece::L];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,11 +58,14 @@ impl Rot {
             Rot::R270 => Rot::R180,
         }
     }
+    pub fn flip(self) -> Self {
+        self.cw().cw()
+    }
 }
 
 // Precomputed block offsets for each tetromino at each rotation.
-// Coordinates are in cells relative to the piece pivot position (x, y).
-// These match common guideline shapes; SRS kicks will be added later.
+// Coordinates are in cells relative to the piece pivot position (x, y), with y growing downward.
+// Rotation states follow SRS (R0 = spawn, flat side down) so the kick data in `kicks` applies.
 // Order of minos is arbitrary but consistent per shape.
 pub const SHAPES: [[[(i16, i16); 4]; 4]; 7] = {
     // I
@@ -55,81 +80,396 @@ pub const SHAPES: [[[(i16, i16); 4]; 4]; 7] = {
         [(0, -1), (0, 0), (0, 1), (0, 2)],
     ];
 
-    // O (pivot centered between 4 blocks; here we use offsets that render correctly)
+    // O (pivot on the bottom-left block so every piece spawns with its base on the pivot row)
     let o = [
-        [(0, 0), (1, 0), (0, 1), (1, 1)],
-        [(0, 0), (1, 0), (0, 1), (1, 1)],
-        [(0, 0), (1, 0), (0, 1), (1, 1)],
-        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, -1), (1, -1), (0, 0), (1, 0)],
+        [(0, -1), (1, -1), (0, 0), (1, 0)],
+        [(0, -1), (1, -1), (0, 0), (1, 0)],
+        [(0, -1), (1, -1), (0, 0), (1, 0)],
     ];
 
     // T
     let t = [
-        [(-1, 0), (0, 0), (1, 0), (0, 1)],
-        [(0, -1), (0, 0), (0, 1), (1, 0)],
         [(-1, 0), (0, 0), (1, 0), (0, -1)],
+        [(0, -1), (0, 0), (0, 1), (1, 0)],
+        [(-1, 0), (0, 0), (1, 0), (0, 1)],
         [(0, -1), (0, 0), (0, 1), (-1, 0)],
     ];
 
     // S
     let s = [
-        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(0, -1), (1, -1), (-1, 0), (0, 0)],
         [(0, -1), (0, 0), (1, 0), (1, 1)],
-        [(-1, -1), (0, -1), (0, 0), (1, 0)],
+        [(0, 0), (1, 0), (-1, 1), (0, 1)],
         [(-1, -1), (-1, 0), (0, 0), (0, 1)],
     ];
 
     // Z
     let z = [
-        [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        [(-1, -1), (0, -1), (0, 0), (1, 0)],
         [(1, -1), (1, 0), (0, 0), (0, 1)],
-        [(-1, 0), (0, 0), (0, -1), (1, -1)],
-        [(-1, -1), (-1, 0), (0, 0), (0, 1)],
+        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(0, -1), (0, 0), (-1, 0), (-1, 1)],
     ];
 
     // J
     let j = [
-        [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        [(-1, -1), (-1, 0), (0, 0), (1, 0)],
         [(0, -1), (1, -1), (0, 0), (0, 1)],
-        [(-1, 0), (0, 0), (1, 0), (1, -1)],
+        [(-1, 0), (0, 0), (1, 0), (1, 1)],
         [(0, -1), (0, 0), (-1, 1), (0, 1)],
     ];
 
     // L
     let l = [
+        [(-1, 0), (0, 0), (1, 0), (1, -1)],
+        [(0, -1), (0, 0), (0, 1), (1, 1)],
+        [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        [(-1, -1), (0, -1), (0, 0), (0, 1)],
+    ];
+
+    [i, o, t, s, z, j, l]
+};
+
+// Arika rotation system (TGM). Same pivot convention as SRS (centre of the 3x3 box, or
+// (1, 1) of the 4x4 box for I and O), but every state rests on the bottom of its box and
+// T, J and L spawn flat side up.
+pub const ARS_SHAPES: [[[(i16, i16); 4]; 4]; 7] = {
+    let i = [
+        [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        [(1, -1), (1, 0), (1, 1), (1, 2)],
+        [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        [(1, -1), (1, 0), (1, 1), (1, 2)],
+    ];
+
+    let o = [
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+    ];
+
+    let t = [
+        [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        [(0, -1), (-1, 0), (0, 0), (0, 1)],
+        [(0, 0), (-1, 1), (0, 1), (1, 1)],
+        [(0, -1), (0, 0), (1, 0), (0, 1)],
+    ];
+
+    let s = [
+        [(0, 0), (1, 0), (-1, 1), (0, 1)],
+        [(-1, -1), (-1, 0), (0, 0), (0, 1)],
+        [(0, 0), (1, 0), (-1, 1), (0, 1)],
+        [(-1, -1), (-1, 0), (0, 0), (0, 1)],
+    ];
+
+    let z = [
+        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(1, -1), (0, 0), (1, 0), (0, 1)],
+        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(1, -1), (0, 0), (1, 0), (0, 1)],
+    ];
+
+    let j = [
         [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        [(0, -1), (0, 0), (-1, 1), (0, 1)],
+        [(-1, 0), (-1, 1), (0, 1), (1, 1)],
+        [(0, -1), (1, -1), (0, 0), (0, 1)],
+    ];
+
+    let l = [
+        [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+        [(-1, -1), (0, -1), (0, 0), (0, 1)],
+        [(1, 0), (-1, 1), (0, 1), (1, 1)],
         [(0, -1), (0, 0), (0, 1), (1, 1)],
+    ];
+
+    [i, o, t, s, z, j, l]
+};
+
+// Nintendo rotation system (NES). Pivot at (0, 0) with the spawn state resting on row 0
+// below it; T, J and L spawn flat side up. Rotation is right-handed: S, Z and I only have
+// two states, and their vertical state sits right of the pivot.
+pub const NRS_SHAPES: [[[(i16, i16); 4]; 4]; 7] = {
+    let i = [
+        [(-2, 0), (-1, 0), (0, 0), (1, 0)],
+        [(0, -2), (0, -1), (0, 0), (0, 1)],
+        [(-2, 0), (-1, 0), (0, 0), (1, 0)],
+        [(0, -2), (0, -1), (0, 0), (0, 1)],
+    ];
+    let o = [[(-1, 0), (0, 0), (-1, 1), (0, 1)]; 4];
+    let t = [
+        [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        [(0, -1), (-1, 0), (0, 0), (0, 1)],
+        [(0, -1), (-1, 0), (0, 0), (1, 0)],
+        [(0, -1), (0, 0), (1, 0), (0, 1)],
+    ];
+    let s = [
+        [(0, 0), (1, 0), (-1, 1), (0, 1)],
+        [(0, -1), (0, 0), (1, 0), (1, 1)],
+        [(0, 0), (1, 0), (-1, 1), (0, 1)],
+        [(0, -1), (0, 0), (1, 0), (1, 1)],
+    ];
+    let z = [
+        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(1, -1), (0, 0), (1, 0), (0, 1)],
+        [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        [(1, -1), (0, 0), (1, 0), (0, 1)],
+    ];
+    let j = [
+        [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        [(0, -1), (0, 0), (-1, 1), (0, 1)],
         [(-1, -1), (-1, 0), (0, 0), (1, 0)],
+        [(0, -1), (1, -1), (0, 0), (0, 1)],
+    ];
+    let l = [
+        [(-1, 0), (0, 0), (1, 0), (-1, 1)],
         [(-1, -1), (0, -1), (0, 0), (0, 1)],
+        [(1, -1), (-1, 0), (0, 0), (1, 0)],
+        [(0, -1), (0, 0), (0, 1), (1, 1)],
     ];
 
     [i, o, t, s, z, j, l]
 };
 
-#[inline]
-pub fn blocks(t: Tetromino, r: Rot) -> [(i16, i16); 4] {
-    SHAPES[t as usize][r as usize]
+/// Piece orientations and the matching kick behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationSystem {
+    /// Super Rotation System (guideline).
+    #[default]
+    Srs,
+    /// Arika Rotation System (TGM).
+    Ars,
+    /// Nintendo Rotation System (NES): no kicks at all.
+    Nrs,
+}
+
+/// Mino coordinates of one piece pose, stored inline so `ActivePiece` stays `Copy`.
+/// Derefs to a slice of the `len` used entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minos {
+    cells: [(i16, i16); MAX_MINOS],
+    len: u8,
+}
+
+impl Minos {
+    /// Copies at most MAX_MINOS cells (piece sets are validated to stay within it).
+    pub fn new(cells: &[(i16, i16)]) -> Self {
+        let mut minos = Self { cells: [(0, 0); MAX_MINOS], len: cells.len().min(MAX_MINOS) as u8 };
+        minos.cells[..minos.len as usize].copy_from_slice(&cells[..minos.len as usize]);
+        minos
+    }
+
+    fn offset(mut self, dx: i16, dy: i16) -> Self {
+        for cell in &mut self.cells[..self.len as usize] {
+            *cell = (cell.0 + dx, cell.1 + dy);
+        }
+        self
+    }
+}
+
+impl Deref for Minos {
+    type Target = [(i16, i16)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.cells[..self.len as usize]
+    }
+}
+
+/// One piece of a set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PieceDef {
+    /// Letter naming the piece in fixed sequences.
+    pub letter: char,
+    /// Mino offsets from the pivot (y grows downward) for R0, R90, R180 and R270. One or two
+    /// states may be given instead of four; they repeat in order.
+    pub rotations: Vec<Vec<(i16, i16)>>,
+    /// RGB colour; None takes the theme's colour for the piece's slot.
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+    /// 90° kick tests, see `kicks::KickTable`. None uses the rotation system's default.
+    #[serde(default)]
+    pub kicks: Option<KickTable>,
+    /// Spins of this piece are checked and scored as T-spins (3x3, T-shaped pieces only).
+    #[serde(default)]
+    pub t_spin: bool,
+}
+
+/// The pieces a game deals: the standard tetrominoes of a rotation system, or a custom set
+/// loaded from a JSON or RON file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PieceSet {
+    #[serde(default)]
+    pub name: String,
+    pub pieces: Vec<PieceDef>,
+    /// Built from the rotation system's shape tables; it is rebuilt when the rules change.
+    #[serde(skip)]
+    pub standard: bool,
+}
+
+impl PieceSet {
+    /// The seven tetrominoes with the shapes and kicks of `sys`.
+    pub fn standard(sys: RotationSystem) -> Self {
+        let shapes = match sys {
+            RotationSystem::Srs => &SHAPES,
+            RotationSystem::Ars => &ARS_SHAPES,
+            RotationSystem::Nrs => &NRS_SHAPES,
+        };
+        let pieces = Piece::TETROMINOES
+            .iter()
+            .zip("IOTSZJL".chars())
+            .map(|(&t, letter)| PieceDef {
+                letter,
+                rotations: shapes[t.index()].iter().map(|state| state.to_vec()).collect(),
+                color: None,
+                kicks: kicks::standard_kicks(sys, t),
+                t_spin: t == Piece::T,
+            })
+            .collect();
+        Self { name: "Standard".to_owned(), pieces, standard: true }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn get(&self, t: Piece) -> &PieceDef {
+        &self.pieces[t.index()]
+    }
+
+    /// Mino offsets of `t` in rotation state `rot`.
+    pub fn minos(&self, t: Piece, rot: Rot) -> Minos {
+        let rotations = &self.get(t).rotations;
+        Minos::new(&rotations[rot as usize % rotations.len()])
+    }
+
+    /// Parse a drill sequence such as "TSZ IOJL"; anything that is not a piece letter of
+    /// this set is skipped. Letters are case-insensitive.
+    pub fn parse_sequence(&self, text: &str) -> Vec<Piece> {
+        text.chars()
+            .filter_map(|c| {
+                let i = self.pieces.iter().position(|p| p.letter.eq_ignore_ascii_case(&c))?;
+                Some(Piece(i as u8))
+            })
+            .collect()
+    }
+
+    /// Check the set can be played: 1 to 255 pieces with distinct letters, each with 1, 2 or
+    /// 4 rotation states of the same 1 to MAX_MINOS minos, and complete kick tables.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.pieces.is_empty() || self.pieces.len() > u8::MAX as usize {
+            bail!("a piece set needs 1 to 255 pieces, found {}", self.pieces.len());
+        }
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let letter = piece.letter;
+            if self.pieces[..i].iter().any(|p| p.letter.eq_ignore_ascii_case(&letter)) {
+                bail!("piece letter {letter:?} is used twice");
+            }
+            if ![1, 2, 4].contains(&piece.rotations.len()) {
+                bail!("piece {letter:?} needs 1, 2 or 4 rotation states");
+            }
+            let count = piece.rotations[0].len();
+            if count == 0 || count > MAX_MINOS || piece.rotations.iter().any(|r| r.len() != count) {
+                bail!("every state of piece {letter:?} needs the same 1 to {MAX_MINOS} minos");
+            }
+            if let Some(table) = &piece.kicks {
+                if table.len() != 8 || table.iter().any(|row| row.is_empty()) {
+                    bail!("kick table of piece {letter:?} needs 8 non-empty rows");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse and validate a set; `ron` picks RON over JSON.
+    pub fn parse(text: &str, ron: bool) -> anyhow::Result<PieceSet> {
+        let set: PieceSet = if ron { ron::from_str(text)? } else { serde_json::from_str(text)? };
+        set.validate()?;
+        Ok(set)
+    }
+}
+
+impl Preset for PieceSet {
+    const KIND: &'static str = "piece set";
+    const EXTENSIONS: &'static [&'static str] = &["json", "ron"];
+
+    fn parse(text: &str, extension: &str) -> anyhow::Result<Self> {
+        PieceSet::parse(text, extension == "ron")
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct ActivePiece {
-    pub t: Tetromino,
+    pub t: Piece,
     pub rot: Rot,
     pub x: i16, // board coordinates
     pub y: i16,
+    /// Offsets of the current rotation state, copied from the piece set.
+    pub minos: Minos,
+    /// Power mino, as an index into `cells()`, rolled at spawn.
+    pub power: Option<(usize, Power)>,
 }
 
 impl ActivePiece {
-    pub fn new(t: Tetromino, x: i16, y: i16) -> Self {
-        Self { t, rot: Rot::R0, x, y }
-    }
-    pub fn cells(&self) -> [(i16, i16); 4] {
-        let offs = blocks(self.t, self.rot);
-        [
-            (self.x + offs[0].0, self.y + offs[0].1),
-            (self.x + offs[1].0, self.y + offs[1].1),
-            (self.x + offs[2].0, self.y + offs[2].1),
-            (self.x + offs[3].0, self.y + offs[3].1),
-        ]
+    pub fn new(set: &PieceSet, t: Piece, x: i16, y: i16) -> Self {
+        Self { t, rot: Rot::R0, x, y, minos: set.minos(t, Rot::R0), power: None }
+    }
+
+    /// The same piece turned to `rot` in place (no collision check or kicks).
+    pub fn rotated(mut self, set: &PieceSet, rot: Rot) -> Self {
+        self.rot = rot;
+        self.minos = set.minos(self.t, rot);
+        self
+    }
+
+    pub fn cells(&self) -> Minos {
+        self.minos.offset(self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_set_matches_shape_tables() {
+        let set = PieceSet::standard(RotationSystem::Srs);
+        assert!(set.validate().is_ok());
+        let p = ActivePiece::new(&set, Piece::T, 4, 5).rotated(&set, Rot::R90);
+        assert_eq!(*p.cells(), [(4, 4), (4, 5), (4, 6), (5, 5)]);
+        assert_eq!(set.parse_sequence("t, s-z"), [Piece::T, Piece::S, Piece::Z]);
+    }
+
+    #[test]
+    fn test_custom_set_from_ron_with_two_states() {
+        let text = r#"(
+            name: "Practice",
+            pieces: [
+                (letter: 'M', rotations: [[(0, 0)]]),
+                (letter: 'D', rotations: [[(0, 0), (1, 0)], [(0, 0), (0, 1)]], color: Some((255, 0, 0))),
+                (letter: 'P', rotations: [[(0, -1), (1, -1), (0, 0), (1, 0), (0, 1)]]),
+            ],
+        )"#;
+        let set = PieceSet::parse(text, true).unwrap();
+        assert_eq!((set.name.as_str(), set.len(), set.standard), ("Practice", 3, false));
+        assert_eq!(*set.minos(Piece(1), Rot::R180), [(0, 0), (1, 0)]);
+        assert_eq!(*set.minos(Piece(1), Rot::R270), [(0, 0), (0, 1)]);
+        assert_eq!(set.minos(Piece(2), Rot::R0).len(), 5);
+    }
+
+    #[test]
+    fn test_invalid_sets_are_rejected() {
+        let json = |pieces: &str| PieceSet::parse(&format!(r#"{{ "pieces": [{pieces}] }}"#), false);
+        assert!(json("").is_err());
+        assert!(json(r#"{ "letter": "A", "rotations": [[[0, 0]], [[0, 0]], [[0, 0]]] }"#).is_err());
+        assert!(json(r#"{ "letter": "A", "rotations": [[[0, 0]], [[0, 0], [1, 0]]] }"#).is_err());
+        assert!(json(r#"{ "letter": "A", "rotations": [[[0, 0]]] }, { "letter": "a", "rotations": [[[0, 0]]] }"#).is_err());
+        assert!(json(r#"{ "letter": "A", "rotations": [[[0, 0]]], "kicks": [[[0, 0]]] }"#).is_err());
+        assert!(json(r#"{ "letter": "A", "rotations": [[[0, 0], [1, 0]]] }"#).is_ok());
     }
 }
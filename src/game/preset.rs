@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context as _;
+
+/// A user preset (rule set, piece set) loaded from a file in the config folder.
+pub trait Preset: Sized {
+    /// What the preset is called in messages, e.g. "rule set".
+    const KIND: &'static str;
+    /// File extensions read, without the dot.
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Parse a file with the given extension. The name is left empty when the file does not
+    /// give one.
+    fn parse(text: &str, extension: &str) -> anyhow::Result<Self>;
+
+    fn name_mut(&mut self) -> &mut String;
+}
+
+/// Every preset in `dir`, sorted by file name. A preset without a name is named after its
+/// file; unreadable or invalid files are reported and skipped.
+pub fn load_dir<T: Preset>(dir: &Path) -> Vec<T> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let extension = |path: &Path| path.extension().and_then(|ext| ext.to_str()).map(str::to_owned);
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| extension(p).is_some_and(|ext| T::EXTENSIONS.contains(&ext.as_str())))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| {
+            let loaded = fs::read_to_string(path)
+                .context("unreadable")
+                .and_then(|text| T::parse(&text, &extension(path).unwrap_or_default()));
+            match loaded {
+                Ok(mut preset) => {
+                    if preset.name_mut().is_empty() {
+                        *preset.name_mut() = path.file_stem()?.to_string_lossy().into_owned();
+                    }
+                    Some(preset)
+                }
+                Err(err) => {
+                    eprintln!("skipping {} {}: {err:#}", T::KIND, path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::PieceSet;
+    use crate::game::rules::RuleSet;
+
+    #[test]
+    fn test_load_dir_names_unnamed_presets_and_skips_bad_files() {
+        let dir = std::env::temp_dir().join(format!("tetris-synthwave-presets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b-fast.json"), r#"{ "lock_delay": 0.2 }"#).unwrap();
+        fs::write(dir.join("a-named.json"), r#"{ "name": "Named" }"#).unwrap();
+        fs::write(dir.join("c-broken.json"), "{").unwrap();
+        fs::write(dir.join("dots.ron"), "(pieces: [(letter: 'D', rotations: [[(0, 0)]])])").unwrap();
+
+        let rules: Vec<String> = load_dir::<RuleSet>(&dir).into_iter().map(|r| r.name).collect();
+        let sets: Vec<String> = load_dir::<PieceSet>(&dir).into_iter().map(|s| s.name).collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(rules, ["Named", "b-fast"]);
+        // The rule set files are not piece sets; only the RON set loads
+        assert_eq!(sets, ["dots"]);
+    }
+}
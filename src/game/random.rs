@@ -1,44 +1,120 @@
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng, thread_rng, Rng};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 
-use super::piece::Tetromino;
+use super::piece::Piece;
+use super::rng::Pcg32;
 
-/// Deterministic 7-bag randomizer. Refill occurs when the pool is empty.
-pub struct SevenBag {
-    rng: StdRng,
-    pool: Vec<Tetromino>,
+/// PCG stream used for piece generation (other game randomness uses its own stream).
+pub const PIECE_STREAM: u64 = 0;
+
+/// The first `count` pieces of a set: everything a generator deals from.
+fn all(count: usize) -> Vec<Piece> {
+    (0..count.clamp(1, u8::MAX as usize) as u8).map(Piece).collect()
 }
 
-impl SevenBag {
-    /// If `seed` is None, a random seed is generated for true randomization.
-    pub fn new(seed: Option<u64>) -> Self {
-        let rng = match seed {
-            Some(s) => StdRng::seed_from_u64(s),
-            None => {
-                // Generate a truly random seed
-                let random_seed = thread_rng().gen::<u64>();
-                StdRng::seed_from_u64(random_seed)
-            }
-        };
-        let mut this = Self { rng, pool: Vec::with_capacity(7) };
+/// Source of upcoming pieces.
+pub trait Randomizer {
+    fn next(&mut self) -> Piece;
+    /// The next `count` pieces `next` will return, without consuming them.
+    fn peek(&self, count: usize) -> Vec<Piece>;
+    /// A new generator of the same kind, starting over. A `seed` of None picks a random one.
+    fn reset(&self, seed: Option<u64>) -> Box<dyn Randomizer>;
+}
+
+/// Peek by running a throwaway copy of the generator.
+fn peek_by_clone<R: Randomizer + Clone>(r: &R, count: usize) -> Vec<Piece> {
+    let mut copy = r.clone();
+    (0..count).map(|_| copy.next()).collect()
+}
+
+/// Fresh seed from OS entropy. Only the seed comes from `rand`; every draw after it
+/// goes through the versioned `Pcg32`.
+pub fn random_seed() -> u64 {
+    thread_rng().gen::<u64>()
+}
+
+/// If `seed` is None, a random seed is generated for true randomization.
+fn seeded_rng(seed: Option<u64>) -> Pcg32 {
+    Pcg32::new(seed.unwrap_or_else(random_seed), PIECE_STREAM)
+}
+
+/// Which generator to use; persisted in settings and picked in the side panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RandomizerKind {
+    SevenBag,
+    FourteenBag,
+    PureRandom,
+    NesReroll,
+    TgmHistory,
+    Fixed,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 6] = [
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::PureRandom,
+        RandomizerKind::NesReroll,
+        RandomizerKind::TgmHistory,
+        RandomizerKind::Fixed,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RandomizerKind::SevenBag => "7-Bag",
+            RandomizerKind::FourteenBag => "14-Bag",
+            RandomizerKind::PureRandom => "Pure Random",
+            RandomizerKind::NesReroll => "NES Reroll",
+            RandomizerKind::TgmHistory => "TGM History",
+            RandomizerKind::Fixed => "Fixed Sequence",
+        }
+    }
+
+    /// Build a generator dealing from a set of `count` pieces. `sequence` is only used by
+    /// `Fixed`; an empty one falls back to a single bag.
+    pub fn build(self, seed: Option<u64>, sequence: &[Piece], count: usize) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::SevenBag => Box::new(Bag::new(1, count, seed)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(2, count, seed)),
+            RandomizerKind::PureRandom => Box::new(PureRandom::new(count, seed)),
+            RandomizerKind::NesReroll => Box::new(NesReroll::new(count, seed)),
+            RandomizerKind::TgmHistory => Box::new(TgmHistory::new(count, seed)),
+            RandomizerKind::Fixed if sequence.is_empty() => Box::new(Bag::new(1, count, seed)),
+            RandomizerKind::Fixed => Box::new(Fixed::new(sequence.to_vec())),
+        }
+    }
+}
+
+/// Shuffled bag holding `copies` of every piece (1 = guideline 7-bag, 2 = 14-bag).
+/// Refill occurs when the pool is empty.
+#[derive(Clone)]
+pub struct Bag {
+    rng: Pcg32,
+    pool: Vec<Piece>,
+    pieces: Vec<Piece>,
+    copies: usize,
+}
+
+impl Bag {
+    pub fn new(copies: usize, count: usize, seed: Option<u64>) -> Self {
+        let pieces = all(count);
+        let mut this = Self { rng: seeded_rng(seed), pool: Vec::with_capacity(pieces.len() * copies), pieces, copies };
         this.refill();
         this
     }
 
     fn refill(&mut self) {
         self.pool.clear();
-        self.pool.extend_from_slice(&[
-            Tetromino::I,
-            Tetromino::O,
-            Tetromino::T,
-            Tetromino::S,
-            Tetromino::Z,
-            Tetromino::J,
-            Tetromino::L,
-        ]);
-        self.pool.shuffle(&mut self.rng);
-    }
-
-    pub fn next(&mut self) -> Tetromino {
+        for _ in 0..self.copies {
+            self.pool.extend_from_slice(&self.pieces);
+        }
+        self.rng.shuffle(&mut self.pool);
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self) -> Piece {
         if self.pool.is_empty() {
             self.refill();
         }
@@ -46,29 +122,271 @@ impl SevenBag {
         self.pool.pop().unwrap()
     }
 
-    /// Peek at the next N pieces without consuming them
-    pub fn peek(&mut self, count: usize) -> Vec<Tetromino> {
-        let mut preview = Vec::with_capacity(count);
-        let mut temp_pool = self.pool.clone();
-        let mut temp_rng = self.rng.clone();
-        
-        for _ in 0..count {
-            if temp_pool.is_empty() {
-                temp_pool.clear();
-                temp_pool.extend_from_slice(&[
-                    Tetromino::I,
-                    Tetromino::O,
-                    Tetromino::T,
-                    Tetromino::S,
-                    Tetromino::Z,
-                    Tetromino::J,
-                    Tetromino::L,
-                ]);
-                temp_pool.shuffle(&mut temp_rng);
+    fn peek(&self, count: usize) -> Vec<Piece> {
+        peek_by_clone(self, count)
+    }
+
+    fn reset(&self, seed: Option<u64>) -> Box<dyn Randomizer> {
+        Box::new(Bag::new(self.copies, self.pieces.len(), seed))
+    }
+}
+
+/// Every piece equally likely on every draw; droughts and floods included.
+#[derive(Clone)]
+pub struct PureRandom {
+    rng: Pcg32,
+    pieces: Vec<Piece>,
+}
+
+impl PureRandom {
+    pub fn new(count: usize, seed: Option<u64>) -> Self {
+        Self { rng: seeded_rng(seed), pieces: all(count) }
+    }
+}
+
+impl Randomizer for PureRandom {
+    fn next(&mut self) -> Piece {
+        self.pieces[self.rng.index(self.pieces.len())]
+    }
+
+    fn peek(&self, count: usize) -> Vec<Piece> {
+        peek_by_clone(self, count)
+    }
+
+    fn reset(&self, seed: Option<u64>) -> Box<dyn Randomizer> {
+        Box::new(PureRandom::new(self.pieces.len(), seed))
+    }
+}
+
+/// NES: roll one of eight slots (one more than there are pieces); a repeat of the last
+/// piece (or the dummy extra slot) triggers a single reroll over the pieces, which is then
+/// taken as-is.
+#[derive(Clone)]
+pub struct NesReroll {
+    rng: Pcg32,
+    pieces: Vec<Piece>,
+    last: Option<Piece>,
+}
+
+impl NesReroll {
+    pub fn new(count: usize, seed: Option<u64>) -> Self {
+        Self { rng: seeded_rng(seed), pieces: all(count), last: None }
+    }
+}
+
+impl Randomizer for NesReroll {
+    fn next(&mut self) -> Piece {
+        let roll = self.rng.index(self.pieces.len() + 1);
+        let piece = match self.pieces.get(roll) {
+            Some(&t) if Some(t) != self.last => t,
+            _ => self.pieces[self.rng.index(self.pieces.len())],
+        };
+        self.last = Some(piece);
+        piece
+    }
+
+    fn peek(&self, count: usize) -> Vec<Piece> {
+        peek_by_clone(self, count)
+    }
+
+    fn reset(&self, seed: Option<u64>) -> Box<dyn Randomizer> {
+        Box::new(NesReroll::new(self.pieces.len(), seed))
+    }
+}
+
+/// TGM3-style: draws from a pool holding 5 of each piece and rerolls up to six times to
+/// avoid the last four pieces. Each drawn slot is refilled with the piece unseen for longest,
+/// so droughts fix themselves. With the standard tetrominoes the first piece is never S, Z
+/// or O; other sets start with an empty history.
+#[derive(Clone)]
+pub struct TgmHistory {
+    rng: Pcg32,
+    pool: Vec<Piece>,
+    count: usize,
+    history: Vec<Piece>, // last four pieces, oldest first
+    order: Vec<Piece>, // pieces from least to most recently drawn
+    first: bool,
+}
+
+const TGM_ROLLS: usize = 6;
+const TGM_HISTORY: usize = 4;
+
+impl TgmHistory {
+    pub fn new(count: usize, seed: Option<u64>) -> Self {
+        let pieces = all(count);
+        let mut pool = Vec::with_capacity(pieces.len() * 5);
+        for _ in 0..5 {
+            pool.extend_from_slice(&pieces);
+        }
+        let standard = pieces.len() == Piece::TETROMINOES.len();
+        Self {
+            rng: seeded_rng(seed),
+            pool,
+            count: pieces.len(),
+            history: if standard { vec![Piece::S, Piece::Z, Piece::S, Piece::Z] } else { Vec::new() },
+            order: Vec::with_capacity(pieces.len()),
+            first: standard,
+        }
+    }
+}
+
+impl Randomizer for TgmHistory {
+    fn next(&mut self) -> Piece {
+        let piece = if self.first {
+            self.first = false;
+            [Piece::I, Piece::J, Piece::L, Piece::T][self.rng.index(4)]
+        } else {
+            let mut i = 0;
+            for roll in 0..TGM_ROLLS {
+                i = self.rng.index(self.pool.len());
+                if !self.history.contains(&self.pool[i]) || roll == TGM_ROLLS - 1 {
+                    break;
+                }
+                if let Some(&droughted) = self.order.first() {
+                    self.pool[i] = droughted;
+                }
             }
-            preview.push(temp_pool.pop().unwrap());
+            let piece = self.pool[i];
+            if let Some(&droughted) = self.order.first() {
+                self.pool[i] = droughted;
+            }
+            piece
+        };
+        self.order.retain(|&t| t != piece);
+        self.order.push(piece);
+        if self.history.len() == TGM_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(piece);
+        piece
+    }
+
+    fn peek(&self, count: usize) -> Vec<Piece> {
+        peek_by_clone(self, count)
+    }
+
+    fn reset(&self, seed: Option<u64>) -> Box<dyn Randomizer> {
+        Box::new(TgmHistory::new(self.count, seed))
+    }
+}
+
+/// Repeats a user-supplied sequence forever, for opener and PC drills.
+#[derive(Clone)]
+pub struct Fixed {
+    sequence: Vec<Piece>,
+    pos: usize,
+}
+
+impl Fixed {
+    /// `sequence` must not be empty.
+    pub fn new(sequence: Vec<Piece>) -> Self {
+        assert!(!sequence.is_empty(), "fixed sequence needs at least one piece");
+        Self { sequence, pos: 0 }
+    }
+}
+
+impl Randomizer for Fixed {
+    fn next(&mut self) -> Piece {
+        let piece = self.sequence[self.pos];
+        self.pos = (self.pos + 1) % self.sequence.len();
+        piece
+    }
+
+    fn peek(&self, count: usize) -> Vec<Piece> {
+        peek_by_clone(self, count)
+    }
+
+    fn reset(&self, _seed: Option<u64>) -> Box<dyn Randomizer> {
+        Box::new(Fixed::new(self.sequence.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::{PieceSet, RotationSystem};
+
+    fn counts(pieces: &[Piece]) -> [usize; 7] {
+        let mut counts = [0; 7];
+        for &t in pieces {
+            counts[t.index()] += 1;
+        }
+        counts
+    }
+
+    fn parse_sequence(text: &str) -> Vec<Piece> {
+        PieceSet::standard(RotationSystem::Srs).parse_sequence(text)
+    }
+
+    #[test]
+    fn test_bags_deal_every_piece_per_cycle() {
+        let mut seven = Bag::new(1, 7, Some(1));
+        let dealt: Vec<_> = (0..21).map(|_| seven.next()).collect();
+        for cycle in dealt.chunks(7) {
+            assert_eq!(counts(cycle), [1; 7]);
+        }
+        let mut fourteen = Bag::new(2, 7, Some(1));
+        let dealt: Vec<_> = (0..14).map(|_| fourteen.next()).collect();
+        assert_eq!(counts(&dealt), [2; 7]);
+    }
+
+    #[test]
+    fn test_peek_matches_next_without_consuming() {
+        for kind in RandomizerKind::ALL {
+            let mut r = kind.build(Some(7), &parse_sequence("TSZ"), 7);
+            let _ = r.next();
+            let peeked = r.peek(10);
+            assert_eq!(r.peek(10), peeked);
+            let dealt: Vec<_> = (0..10).map(|_| r.next()).collect();
+            assert_eq!(dealt, peeked, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_fixed_sequence_loops_and_skips_junk() {
+        let mut r = Fixed::new(parse_sequence("t, s-z"));
+        let dealt: Vec<_> = (0..5).map(|_| r.next()).collect();
+        assert_eq!(dealt, [Piece::T, Piece::S, Piece::Z, Piece::T, Piece::S]);
+    }
+
+    #[test]
+    fn test_generators_deal_from_any_set_size() {
+        for kind in RandomizerKind::ALL {
+            let mut r = kind.build(Some(3), &[], 3);
+            assert!((0..50).all(|_| r.next().index() < 3), "{:?}", kind);
+        }
+        let mut bag = Bag::new(1, 12, Some(3));
+        let mut dealt: Vec<_> = (0..12).map(|_| bag.next().index()).collect();
+        dealt.sort();
+        assert_eq!(dealt, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_tgm_never_starts_with_s_z_or_o() {
+        for seed in 0..50 {
+            let first = TgmHistory::new(7, Some(seed)).next();
+            assert!(!matches!(first, Piece::S | Piece::Z | Piece::O));
+        }
+    }
+
+    /// Golden sequences: these must never change for a given PRNG_VERSION. If one does,
+    /// stored seeds would deal different pieces, so bump `rng::PRNG_VERSION` instead.
+    #[test]
+    fn test_golden_sequences_for_known_seeds() {
+        let golden = [
+            (RandomizerKind::SevenBag, "JTZLSIOZOTIJSL"),
+            (RandomizerKind::FourteenBag, "JILTSJTSOILZZO"),
+            (RandomizerKind::PureRandom, "JOOISOZSILLZLT"),
+            (RandomizerKind::NesReroll, "OTLTOTIZITZOTZ"),
+            (RandomizerKind::TgmHistory, "JOISZLJTOTZJII"),
+        ];
+        for (kind, expected) in golden {
+            let mut r = kind.build(Some(12345), &[], 7);
+            let dealt: Vec<_> = (0..14).map(|_| r.next()).collect();
+            assert_eq!(dealt, parse_sequence(expected), "{:?}", kind);
         }
-        
-        preview
+        let mut bag = Bag::new(1, 7, Some(0));
+        let dealt: Vec<_> = (0..14).map(|_| bag.next()).collect();
+        assert_eq!(dealt, parse_sequence("JZTISLOZLIOTSJ"));
     }
 }
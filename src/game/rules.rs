@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::GarbageHoles;
+use super::gravity::{GravityCurve, NES_FRAMES};
+use super::handling::Handling;
+use super::piece::RotationSystem;
+use super::preset::Preset;
+use super::random::RandomizerKind;
+use super::scoring::ScoringTable;
+use super::{DEFAULT_LOCK_DELAY, MAX_LOCK_RESETS};
+
+/// How the level follows the line count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LevelProgression {
+    /// One level per `lines_per_level` lines on top of the start level.
+    #[default]
+    Fixed,
+    /// NES: from start level S the first level-up comes at min(10S + 10, max(100, 10S - 50))
+    /// lines, then one every `lines_per_level` lines.
+    Nes,
+}
+
+impl LevelProgression {
+    pub fn level(self, start: u32, lines: u32, per_level: u32) -> u32 {
+        let per_level = per_level.max(1);
+        match self {
+            LevelProgression::Fixed => start + lines / per_level,
+            LevelProgression::Nes => {
+                let first = (start * 10 + 10).min(100.max((start * 10).saturating_sub(50)));
+                match lines.checked_sub(first) {
+                    Some(past) => start + 1 + past / per_level,
+                    None => start,
+                }
+            }
+        }
+    }
+}
+
+/// Every rule that differs between game styles. Fields missing from a user preset fall
+/// back to the guideline values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    pub name: String,
+    pub rotation: RotationSystem,
+    /// Try the rotation system's kicks when a 90° rotation collides.
+    pub kicks: bool,
+    /// Allow the 180° rotate input at all.
+    pub rotate_180: bool,
+    /// Use the SRS+ 180° kick table; without it a 180° spin only succeeds in place.
+    pub flip_kicks: bool,
+    /// Seconds a grounded piece waits before locking.
+    pub lock_delay: f32,
+    /// Lock as soon as gravity or soft drop fails to move the piece down (NES), instead of
+    /// waiting out `lock_delay`.
+    pub gravity_lock: bool,
+    /// Moves or rotations that restart the lock timer per piece; 0 means only stepping
+    /// down a row does.
+    pub lock_resets: u32,
+    pub hold: bool,
+    /// Show the ghost piece (when the player has it switched on).
+    pub ghost: bool,
+    /// DAS/ARR/SDF imposed by the rules; None leaves the player's handling settings in charge.
+    pub handling: Option<Handling>,
+    /// Seconds per row while soft dropping, whatever the gravity (NES: every other frame).
+    /// None makes soft drop the handling's SDF times gravity speed.
+    pub soft_drop_rate: Option<f32>,
+    /// Allow the hard drop input at all.
+    pub hard_drop: bool,
+    /// Initial rotation / hold: inputs held during ARE apply to the next spawn.
+    pub irs: bool,
+    pub ihs: bool,
+    /// Next pieces shown.
+    pub preview: usize,
+    pub randomizer: RandomizerKind,
+    pub scoring: ScoringTable,
+    /// Lines to clear per level.
+    pub lines_per_level: u32,
+    pub level_progression: LevelProgression,
+    /// Number shown for level 0, in play and on the level select: 1 for guideline play,
+    /// 0 as on the NES.
+    pub first_level: u32,
+    pub gravity: GravityCurve,
+    /// Seconds between a lock and the next spawn (ARE).
+    pub entry_delay: f32,
+    /// NES: the entry delay grows by 2 frames for every 4 rows the piece locked above the
+    /// bottom two, up to 8 frames more.
+    pub entry_delay_by_height: bool,
+    /// Extra seconds added to the spawn wait when the lock cleared lines.
+    pub line_clear_delay: f32,
+    /// Garbage rows the board starts with, for digging practice.
+    pub start_garbage: u32,
+    pub garbage_holes: GarbageHoles,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::guideline()
+    }
+}
+
+impl RuleSet {
+    /// Modern guideline play: SRS with 180° kicks, hold, five previews, 7-bag.
+    pub fn guideline() -> Self {
+        Self {
+            name: "Guideline".to_owned(),
+            rotation: RotationSystem::Srs,
+            kicks: true,
+            rotate_180: true,
+            flip_kicks: true,
+            lock_delay: DEFAULT_LOCK_DELAY,
+            gravity_lock: false,
+            lock_resets: MAX_LOCK_RESETS,
+            hold: true,
+            ghost: true,
+            handling: None,
+            soft_drop_rate: None,
+            hard_drop: true,
+            irs: true,
+            ihs: true,
+            preview: 5,
+            randomizer: RandomizerKind::SevenBag,
+            scoring: ScoringTable::guideline(),
+            lines_per_level: 10,
+            level_progression: LevelProgression::Fixed,
+            first_level: 1,
+            gravity: GravityCurve::Guideline,
+            entry_delay: 0.0,
+            entry_delay_by_height: false,
+            line_clear_delay: 0.0,
+            start_garbage: 0,
+            garbage_holes: GarbageHoles::Clean,
+        }
+    }
+
+    /// NES Tetris: Nintendo rotation without kicks, no hold or ghost, one preview, NES
+    /// gravity, scoring and level transitions, 16/6 frame DAS and height-based ARE.
+    pub fn classic() -> Self {
+        Self {
+            name: "Classic".to_owned(),
+            rotation: RotationSystem::Nrs,
+            kicks: false,
+            rotate_180: false,
+            flip_kicks: false,
+            gravity_lock: true,
+            lock_resets: 0,
+            hold: false,
+            ghost: false,
+            // Soft drop speed comes from `soft_drop_rate`, so the SDF goes unused
+            handling: Some(Handling { das: 16.0 / 60.0, arr: 6.0 / 60.0, ..Handling::default() }),
+            soft_drop_rate: Some(2.0 / 60.0),
+            hard_drop: false,
+            irs: false,
+            ihs: false,
+            preview: 1,
+            randomizer: RandomizerKind::NesReroll,
+            scoring: ScoringTable::classic(),
+            level_progression: LevelProgression::Nes,
+            first_level: 0,
+            gravity: GravityCurve::Frames(NES_FRAMES.to_vec()),
+            entry_delay: 10.0 / 60.0,
+            entry_delay_by_height: true,
+            line_clear_delay: 18.0 / 60.0,
+            ..Self::guideline()
+        }
+    }
+
+    /// Arcade-style rules: ARS, step reset, IRS/IHS, history randomizer and a quick climb
+    /// to 20G.
+    pub fn tgm_like() -> Self {
+        Self {
+            name: "TGM-like".to_owned(),
+            rotation: RotationSystem::Ars,
+            rotate_180: false,
+            flip_kicks: false,
+            lock_resets: 0,
+            preview: 1,
+            randomizer: RandomizerKind::TgmHistory,
+            scoring: ScoringTable {
+                t_spins: false,
+                back_to_back: false,
+                perfect_clear: false,
+                hard_drop: 1,
+                ..ScoringTable::guideline()
+            },
+            gravity: GravityCurve::Table(vec![
+                1.0, 0.5, 0.25, 0.125, 0.0625, 1.0 / 32.0, 1.0 / 60.0, 1.0 / 120.0, 1.0 / 240.0, 0.0,
+            ]),
+            entry_delay: 25.0 / 60.0,
+            line_clear_delay: 40.0 / 60.0,
+            ..Self::guideline()
+        }
+    }
+
+    /// Guideline play on a board that starts with ten rows of messy garbage to dig through.
+    pub fn dig() -> Self {
+        Self {
+            name: "Dig".to_owned(),
+            start_garbage: 10,
+            garbage_holes: GarbageHoles::Messy(0.3),
+            ..Self::guideline()
+        }
+    }
+
+    /// The presets that ship with the game.
+    pub fn builtin() -> Vec<RuleSet> {
+        vec![Self::guideline(), Self::classic(), Self::tgm_like(), Self::dig()]
+    }
+
+    /// Parse a preset. The name is left empty when the JSON does not give one.
+    pub fn from_json(json: &str) -> serde_json::Result<RuleSet> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let named = value.get("name").is_some();
+        let mut rules: RuleSet = serde_json::from_value(value)?;
+        if !named {
+            rules.name.clear();
+        }
+        Ok(rules)
+    }
+}
+
+impl Preset for RuleSet {
+    const KIND: &'static str = "rule set";
+    const EXTENSIONS: &'static [&'static str] = &["json"];
+
+    fn parse(text: &str, _extension: &str) -> anyhow::Result<Self> {
+        Ok(Self::from_json(text)?)
+    }
+
+    fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_round_trip_through_json() {
+        for rules in RuleSet::builtin() {
+            let json = serde_json::to_string(&rules).unwrap();
+            assert_eq!(RuleSet::from_json(&json).unwrap(), rules);
+        }
+    }
+
+    #[test]
+    fn test_partial_preset_fills_in_guideline_values() {
+        let rules = RuleSet::from_json(r#"{ "hold": false, "preview": 2 }"#).unwrap();
+        assert_eq!(rules.name, "");
+        assert!(!rules.hold);
+        assert_eq!(rules.preview, 2);
+        assert_eq!(rules.scoring, ScoringTable::guideline());
+        assert_eq!(rules.rotation, RotationSystem::Srs);
+    }
+
+    #[test]
+    fn test_nes_level_transition() {
+        let nes = LevelProgression::Nes;
+        assert_eq!((nes.level(0, 9, 10), nes.level(0, 10, 10), nes.level(0, 25, 10)), (0, 1, 2));
+        // Level 5 waits until 60 lines; 9 and 15 until 100; 18 until 130; 19 until 140
+        assert_eq!((nes.level(5, 59, 10), nes.level(5, 60, 10)), (5, 6));
+        assert_eq!((nes.level(9, 99, 10), nes.level(9, 100, 10)), (9, 10));
+        assert_eq!((nes.level(15, 99, 10), nes.level(15, 100, 10)), (15, 16));
+        assert_eq!((nes.level(18, 129, 10), nes.level(18, 130, 10)), (18, 19));
+        assert_eq!((nes.level(19, 139, 10), nes.level(19, 150, 10)), (19, 21));
+        assert_eq!(LevelProgression::Fixed.level(3, 25, 10), 5);
+    }
+}
@@ -0,0 +1,482 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::piece::ActivePiece;
+
+/// Seconds allowed between clears to keep a combo chain alive.
+pub const CHAIN_WINDOW: f32 = 2.5;
+/// Highest combo chain multiplier.
+pub const CHAIN_MAX: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// Points a rule set awards. Clear, T-spin and perfect clear points are multiplied by
+/// the 1-based level; drop points are not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringTable {
+    /// Plain clears of 1, 2, 3 and 4 (or more) lines.
+    pub lines: [u32; 4],
+    /// Detect and score T-spins; otherwise a spun T scores as a plain clear.
+    pub t_spins: bool,
+    /// 1.5x for a difficult clear following another.
+    pub back_to_back: bool,
+    /// Bonus per combo step; 0 disables combos.
+    pub combo: u32,
+    pub perfect_clear: bool,
+    /// Points per row of soft and hard drop.
+    pub soft_drop: u32,
+    pub hard_drop: u32,
+}
+
+impl Default for ScoringTable {
+    fn default() -> Self {
+        Self::guideline()
+    }
+}
+
+impl ScoringTable {
+    pub fn guideline() -> Self {
+        Self {
+            lines: [100, 300, 500, 800],
+            t_spins: true,
+            back_to_back: true,
+            combo: 50,
+            perfect_clear: true,
+            soft_drop: 1,
+            hard_drop: 2,
+        }
+    }
+
+    /// NES scoring: 40/100/300/1200, soft drop rows only, no bonuses.
+    pub fn classic() -> Self {
+        Self {
+            lines: [40, 100, 300, 1200],
+            t_spins: false,
+            back_to_back: false,
+            combo: 0,
+            perfect_clear: false,
+            soft_drop: 1,
+            hard_drop: 0,
+        }
+    }
+}
+
+/// What a single lock achieved: lines cleared and whether it was a T-spin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClearKind {
+    pub lines: u32,
+    pub spin: Spin,
+}
+
+impl ClearKind {
+    /// Points before the level multiplier. T-spins use the guideline values.
+    pub fn base_points(&self, table: &ScoringTable) -> u32 {
+        match (self.spin, self.lines) {
+            (Spin::None, 0) => 0,
+            (Spin::None, n) => table.lines[n.min(4) as usize - 1],
+            (Spin::Mini, 0) => 100,
+            (Spin::Mini, 1) => 200,
+            (Spin::Mini, _) => 400,
+            (Spin::Full, 0) => 400,
+            (Spin::Full, 1) => 800,
+            (Spin::Full, 2) => 1200,
+            (Spin::Full, _) => 1600,
+        }
+    }
+
+    /// Tetrises and line-clearing T-spins keep a back-to-back chain alive.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.lines > 0 && self.spin != Spin::None)
+    }
+
+    /// Callout text such as "T-SPIN DOUBLE"; plain singles to triples get none.
+    pub fn name(&self) -> Option<String> {
+        let count = match self.lines {
+            0 => "",
+            1 => " SINGLE",
+            2 => " DOUBLE",
+            3 => " TRIPLE",
+            _ => " TETRIS",
+        };
+        match self.spin {
+            Spin::Full => Some(format!("T-SPIN{}", count)),
+            Spin::Mini => Some(format!("T-SPIN MINI{}", count)),
+            Spin::None if self.lines >= 4 => Some("TETRIS".to_owned()),
+            Spin::None => None,
+        }
+    }
+}
+
+/// Points for step `step` (2 for the first follow-up) of a cascade clear: the plain line
+/// clear value times the step, before the level multiplier.
+pub fn cascade_points(lines: u32, step: u32, table: &ScoringTable) -> u32 {
+    ClearKind { lines, spin: Spin::None }.base_points(table) * step
+}
+
+/// Guideline perfect clear bonus before the level multiplier, paid on top of the line clear.
+/// A back-to-back tetris perfect clear earns the larger 3200 bonus.
+pub fn perfect_clear_points(lines: u32, back_to_back: bool) -> u32 {
+    match lines {
+        0 => 0,
+        1 => 800,
+        2 => 1200,
+        3 => 1800,
+        _ if back_to_back => 3200,
+        _ => 2000,
+    }
+}
+
+/// Points earned by one lock, plus the streak state that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Award {
+    pub points: u64,
+    pub back_to_back: bool,
+    pub combo: u32,
+}
+
+/// Consecutive-clear combo and back-to-back chain, updated on every lock.
+#[derive(Debug, Clone, Copy)]
+pub struct Streaks {
+    combo: i32, // -1 = no active combo; 0 = first clear; n = n-th consecutive clear after it
+    b2b: u32,   // consecutive difficult clears with no easy clear in between
+}
+
+impl Streaks {
+    pub fn new() -> Self {
+        Self { combo: -1, b2b: 0 }
+    }
+
+    /// Current combo count (0 when no combo is running).
+    pub fn combo(&self) -> u32 {
+        self.combo.max(0) as u32
+    }
+
+    /// Back-to-back bonuses earned in a row.
+    pub fn back_to_back(&self) -> u32 {
+        self.b2b.saturating_sub(1)
+    }
+
+    /// Register a lock and score it: difficult clears following another difficult clear earn
+    /// 1.5x, and each consecutive clear adds `table.combo` x combo (when the table enables
+    /// them). `level_mult` is the 1-based level.
+    pub fn score(&mut self, clear: ClearKind, level_mult: u64, table: &ScoringTable) -> Award {
+        let mut points = clear.base_points(table) as u64 * level_mult;
+        let mut back_to_back = false;
+
+        if clear.lines == 0 {
+            // Zero-line locks end the combo but leave the back-to-back chain alone
+            self.combo = -1;
+            return Award { points, back_to_back, combo: 0 };
+        }
+
+        if clear.is_difficult() && table.back_to_back {
+            if self.b2b > 0 {
+                points = points * 3 / 2;
+                back_to_back = true;
+            }
+            self.b2b += 1;
+        } else {
+            self.b2b = 0;
+        }
+
+        self.combo += 1;
+        points += table.combo as u64 * self.combo() as u64 * level_mult;
+        let combo = if table.combo > 0 { self.combo() } else { 0 };
+        Award { points, back_to_back, combo }
+    }
+}
+
+/// Optional timed combo chain: every clear inside the window raises the multiplier
+/// (up to CHAIN_MAX); letting the window run out drops it back to 1x.
+#[derive(Debug, Clone, Copy)]
+pub struct ComboChain {
+    multiplier: u32,
+    timer: f32, // seconds left in the window; 0 = no chain
+}
+
+impl ComboChain {
+    pub fn new() -> Self {
+        Self { multiplier: 1, timer: 0.0 }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        self.multiplier
+    }
+
+    /// Fraction of the window left, 1.0 right after a clear.
+    pub fn remaining(&self) -> f32 {
+        self.timer / CHAIN_WINDOW
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        if self.timer > 0.0 {
+            self.timer -= dt;
+            if self.timer <= 0.0 {
+                *self = Self::new();
+            }
+        }
+    }
+
+    /// Register a line clear and return the multiplier that applies to it.
+    pub fn on_clear(&mut self) -> u32 {
+        if self.timer > 0.0 {
+            self.multiplier = (self.multiplier + 1).min(CHAIN_MAX);
+        }
+        self.timer = CHAIN_WINDOW;
+        self.multiplier
+    }
+}
+
+/// Three-corner T-spin check for a T (or a set's T-spin piece, see `PieceDef::t_spin`)
+/// about to lock. `last_rotation` is `Some(final_kick)` if its last successful action was a
+/// rotation, with `final_kick` set when only the last test of a kick table made it fit, and
+/// `None` after a shift/drop. The corners are taken around the centre mino (the one with three
+/// neighbours), so any rotation system's T works. Three occupied corners make a T-spin; it's
+/// a Mini unless both corners beside the nub are filled or the final kick test was used.
+pub fn t_spin(board: &Board, piece: &ActivePiece, last_rotation: Option<bool>) -> Spin {
+    let Some(final_kick) = last_rotation else {
+        return Spin::None;
+    };
+    let cells = piece.cells();
+    let dirs = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    let has = |x: i16, y: i16| cells.contains(&(x, y));
+    let Some(&(cx, cy)) =
+        cells.iter().find(|&&(x, y)| dirs.iter().filter(|&&(dx, dy)| has(x + dx, y + dy)).count() == 3)
+    else {
+        return Spin::None;
+    };
+    let Some(&(nx, ny)) = dirs.iter().find(|&&(dx, dy)| has(cx + dx, cy + dy) && !has(cx - dx, cy - dy)) else {
+        return Spin::None;
+    };
+    let occupied = |dx: i16, dy: i16| {
+        let (x, y) = (cx + dx, cy + dy);
+        !board.in_bounds(x, y) || board.get(x, y).is_some()
+    };
+    let corners = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+    if corners.iter().filter(|&&(dx, dy)| occupied(dx, dy)).count() < 3 {
+        return Spin::None;
+    }
+    // The two front corners lie on the nub's side of the centre
+    let mut front = corners.iter().filter(|&&(dx, dy)| dx * nx + dy * ny > 0);
+    if front.all(|&(dx, dy)| occupied(dx, dy)) || final_kick {
+        Spin::Full
+    } else {
+        Spin::Mini
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::{Cell, CellKind};
+    use crate::game::piece::{Piece, PieceSet, Rot, RotationSystem};
+
+    fn board_from(rows: &[&str]) -> Board {
+        let mut board = Board::new();
+        let top = board.h - rows.len() as i16;
+        for (i, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    board.set(x as i16, top + i as i16, Some(Cell { kind: CellKind::Piece(Piece::O), power: None }));
+                }
+            }
+        }
+        board
+    }
+
+    fn t(rot: Rot, x: i16, y: i16) -> ActivePiece {
+        let set = PieceSet::standard(RotationSystem::Srs);
+        ActivePiece::new(&set, Piece::T, x, y).rotated(&set, rot)
+    }
+
+    #[test]
+    fn test_perfect_clear_bonus_table() {
+        let bonuses: Vec<u32> = (1..=4).map(|n| perfect_clear_points(n, false)).collect();
+        assert_eq!(bonuses, [800, 1200, 1800, 2000]);
+        assert_eq!(perfect_clear_points(4, true), 3200);
+        assert_eq!(perfect_clear_points(0, true), 0);
+    }
+
+    #[test]
+    fn test_tsd_slot_is_full_spin() {
+        // T pointing down into a classic T-spin double slot
+        let board = board_from(&[
+            "##........",
+            "#...######",
+            "##.#######",
+        ]);
+        let y = board.h - 2;
+        assert_eq!(t_spin(&board, &t(Rot::R180, 2, y), Some(false)), Spin::Full);
+    }
+
+    #[test]
+    fn test_ars_t_uses_its_own_nub() {
+        // The ARS T points down at R0 and its R180 centre sits below the pivot
+        let ars = PieceSet::standard(RotationSystem::Ars);
+        let slot = board_from(&[
+            "##........",
+            "#...######",
+            "##.#######",
+        ]);
+        let y = slot.h - 2;
+        let down = ActivePiece::new(&ars, Piece::T, 2, y);
+        assert_eq!(t_spin(&slot, &down, Some(false)), Spin::Full);
+
+        let back = board_from(&[
+            "#.........",
+            "..........",
+            "#.#.......",
+        ]);
+        let up = ActivePiece::new(&ars, Piece::T, 1, y - 1).rotated(&ars, Rot::R180);
+        assert_eq!(t_spin(&back, &up, Some(false)), Spin::Mini);
+    }
+
+    #[test]
+    fn test_needs_rotation_as_last_action() {
+        let board = board_from(&[
+            "##........",
+            "#...######",
+            "##.#######",
+        ]);
+        let y = board.h - 2;
+        assert_eq!(t_spin(&board, &t(Rot::R180, 2, y), None), Spin::None);
+    }
+
+    #[test]
+    fn test_back_corners_only_is_mini() {
+        // Nub points up; only the two bottom corners plus one top corner are filled
+        let board = board_from(&[
+            "#.........",
+            "..........",
+            "#.#.......",
+        ]);
+        let y = board.h - 2;
+        assert_eq!(t_spin(&board, &t(Rot::R0, 1, y), Some(false)), Spin::Mini);
+    }
+
+    #[test]
+    fn test_last_kick_upgrades_mini_to_full() {
+        let board = board_from(&[
+            "#.........",
+            "..........",
+            "#.#.......",
+        ]);
+        let y = board.h - 2;
+        assert_eq!(t_spin(&board, &t(Rot::R0, 1, y), Some(true)), Spin::Full);
+    }
+
+    #[test]
+    fn test_two_corners_is_not_a_spin() {
+        let board = board_from(&[
+            "..........",
+            "..........",
+            "#.#.......",
+        ]);
+        let y = board.h - 2;
+        assert_eq!(t_spin(&board, &t(Rot::R0, 1, y), Some(false)), Spin::None);
+    }
+
+    #[test]
+    fn test_floor_counts_as_occupied() {
+        // Flat T on the floor: both bottom corners are out of bounds
+        let empty = Board::new();
+        let y = empty.h - 1;
+        assert_eq!(t_spin(&empty, &t(Rot::R0, 1, y), Some(false)), Spin::None);
+        let board = board_from(&["#.........", ".........."]);
+        assert_eq!(t_spin(&board, &t(Rot::R0, 1, y), Some(false)), Spin::Mini);
+    }
+
+    #[test]
+    fn test_guideline_points() {
+        let table = ScoringTable::guideline();
+        let k = |lines, spin| ClearKind { lines, spin }.base_points(&table);
+        assert_eq!((k(0, Spin::Full), k(2, Spin::Full), k(3, Spin::Full)), (400, 1200, 1600));
+        assert_eq!((k(0, Spin::Mini), k(1, Spin::Mini), k(2, Spin::Mini)), (100, 200, 400));
+        assert_eq!((k(1, Spin::None), k(4, Spin::None)), (100, 800));
+    }
+
+    #[test]
+    fn test_combo_adds_fifty_per_step_and_resets_on_miss() {
+        let table = ScoringTable::guideline();
+        let mut streaks = Streaks::new();
+        let single = ClearKind { lines: 1, spin: Spin::None };
+        let miss = ClearKind { lines: 0, spin: Spin::None };
+        assert_eq!(streaks.score(single, 1, &table).points, 100);
+        assert_eq!(streaks.score(single, 1, &table).points, 150);
+        assert_eq!(streaks.score(single, 2, &table).points, 2 * (100 + 100));
+        assert_eq!(streaks.combo(), 2);
+        assert_eq!(streaks.score(miss, 1, &table).points, 0);
+        assert_eq!(streaks.combo(), 0);
+        assert_eq!(streaks.score(single, 1, &table).points, 100);
+    }
+
+    #[test]
+    fn test_back_to_back_survives_spins_and_breaks_on_easy_clear() {
+        let table = ScoringTable::guideline();
+        let mut streaks = Streaks::new();
+        let tetris = ClearKind { lines: 4, spin: Spin::None };
+        let tsd = ClearKind { lines: 2, spin: Spin::Full };
+        let tspin_zero = ClearKind { lines: 0, spin: Spin::Full };
+        let double = ClearKind { lines: 2, spin: Spin::None };
+
+        assert!(!streaks.score(tetris, 1, &table).back_to_back);
+        streaks.score(tspin_zero, 1, &table);
+        let award = streaks.score(tsd, 1, &table);
+        assert!(award.back_to_back);
+        assert_eq!(award.points, 1800); // combo was reset by the zero-line spin
+        assert_eq!(streaks.back_to_back(), 1);
+
+        streaks.score(double, 1, &table);
+        assert_eq!(streaks.back_to_back(), 0);
+        assert!(!streaks.score(tetris, 1, &table).back_to_back);
+    }
+
+    #[test]
+    fn test_classic_table_has_no_bonuses() {
+        let table = ScoringTable::classic();
+        let mut streaks = Streaks::new();
+        let tetris = ClearKind { lines: 4, spin: Spin::None };
+        assert_eq!(streaks.score(tetris, 1, &table).points, 1200);
+        let award = streaks.score(tetris, 2, &table);
+        assert_eq!((award.points, award.back_to_back, award.combo), (2400, false, 0));
+    }
+
+    #[test]
+    fn test_chain_grows_inside_window_and_caps() {
+        let mut chain = ComboChain::new();
+        assert_eq!(chain.on_clear(), 1);
+        for expected in 2..=CHAIN_MAX {
+            chain.tick(CHAIN_WINDOW - 0.5);
+            assert_eq!(chain.on_clear(), expected);
+        }
+        chain.tick(1.0);
+        assert_eq!(chain.on_clear(), CHAIN_MAX);
+        assert_eq!(chain.remaining(), 1.0);
+    }
+
+    #[test]
+    fn test_chain_resets_when_window_expires() {
+        let mut chain = ComboChain::new();
+        chain.on_clear();
+        chain.on_clear();
+        assert_eq!(chain.multiplier(), 2);
+        chain.tick(CHAIN_WINDOW);
+        assert_eq!((chain.multiplier(), chain.remaining()), (1, 0.0));
+        assert_eq!(chain.on_clear(), 1);
+    }
+
+    #[test]
+    fn test_callout_names() {
+        let name = |lines, spin| ClearKind { lines, spin }.name();
+        assert_eq!(name(2, Spin::Full).as_deref(), Some("T-SPIN DOUBLE"));
+        assert_eq!(name(0, Spin::Mini).as_deref(), Some("T-SPIN MINI"));
+        assert_eq!(name(4, Spin::None).as_deref(), Some("TETRIS"));
+        assert_eq!(name(2, Spin::None), None);
+    }
+}
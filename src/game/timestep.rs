@@ -0,0 +1,46 @@
+/// Simulation tick length: the engine always advances in steps of exactly this many seconds.
+pub const TICK_DT: f32 = 1.0 / 60.0;
+/// Most ticks run for a single frame. Time beyond this (a window drag, a breakpoint) is
+/// dropped, so a hitch pauses the game instead of fast-forwarding it.
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Accumulates frame time and hands it out as whole simulation ticks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedTimestep {
+    acc: f64, // seconds banked towards the next tick
+}
+
+impl FixedTimestep {
+    /// Bank `frame_dt` seconds and return how many ticks to run now.
+    pub fn advance(&mut self, frame_dt: f32) -> u32 {
+        let tick = TICK_DT as f64;
+        self.acc += frame_dt.max(0.0) as f64;
+        let ticks = (self.acc / tick) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            self.acc = 0.0;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.acc -= ticks as f64 * tick;
+        ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banks_partial_ticks_across_frames() {
+        let mut step = FixedTimestep::default();
+        // 144 Hz frames: most frames run no tick, but none of the time is lost
+        let ticks: u32 = (0..144).map(|_| step.advance(1.0 / 144.0)).sum();
+        assert!((59..=60).contains(&ticks));
+    }
+
+    #[test]
+    fn test_long_hitch_is_capped() {
+        let mut step = FixedTimestep::default();
+        assert_eq!(step.advance(2.0), MAX_TICKS_PER_FRAME);
+        assert_eq!(step.advance(0.0), 0);
+    }
+}
@@ -4,6 +4,7 @@
 mod ui;
 mod game;
 mod app;
+mod settings;
 
 use egui::IconData;
 
@@ -16,8 +17,8 @@ fn main() -> eframe::Result<()> {
             let (width, height) = img.dimensions();
             IconData {
                 rgba: img.into_raw(),
-                width: width as u32,
-                height: height as u32,
+                width,
+                height,
             }
         }
         Err(err) => {
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use crate::game::handling::Handling;
+use crate::game::piece::{PieceSet, RotationSystem};
+use crate::game::preset;
+use crate::game::random::RandomizerKind;
+use crate::game::rules::RuleSet;
+use crate::game::{BOARD_H, BOARD_W};
+use crate::ui::theme::ThemeKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GravityMode {
+    Normal,
+    Pulse,
+}
+
+/// Key names (as in `egui::Key::name`, e.g. "Left", "Space", "X") for each action.
+/// An empty name leaves the action unbound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub move_left: String,
+    pub move_right: String,
+    pub soft_drop: String,
+    pub hard_drop: String,
+    pub rotate_cw: String,
+    /// Second clockwise key, Up by default.
+    pub rotate_cw_alt: String,
+    pub rotate_ccw: String,
+    pub rotate_180: String,
+    pub hold: String,
+    pub pause: String,
+    pub restart: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let key = |k: egui::Key| k.name().to_owned();
+        Self {
+            move_left: key(egui::Key::ArrowLeft),
+            move_right: key(egui::Key::ArrowRight),
+            soft_drop: key(egui::Key::ArrowDown),
+            hard_drop: key(egui::Key::Space),
+            rotate_cw: key(egui::Key::X),
+            rotate_cw_alt: key(egui::Key::ArrowUp),
+            rotate_ccw: key(egui::Key::Z),
+            rotate_180: key(egui::Key::A),
+            hold: key(egui::Key::C),
+            pause: key(egui::Key::P),
+            restart: key(egui::Key::R),
+        }
+    }
+}
+
+/// Persistent user preferences, stored as JSON in the OS config directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemeKind,
+    pub ghost_enabled: bool,
+    pub peek_enabled: bool,
+    pub gravity_mode: GravityMode,
+    /// Sticky gravity after clears, with chain reactions.
+    pub cascade: bool,
+    pub combo_chain: bool,
+    pub handling: Handling,
+    pub keybindings: KeyBindings,
+    /// Name of the rule set to play; see `rulesets_dir` for user presets.
+    pub ruleset: String,
+    /// Level a new game starts on (0 to 19).
+    pub start_level: u32,
+    /// Name of the piece set to deal; see `piece_sets` for user sets.
+    pub piece_set: String,
+    pub randomizer: RandomizerKind,
+    /// Piece letters dealt in a loop by the fixed-sequence randomizer.
+    pub fixed_sequence: String,
+    /// Restarting replays the current seed.
+    pub keep_seed: bool,
+    /// Visible board size; clamped to the supported range when a game starts.
+    pub board_width: i16,
+    pub board_height: i16,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: ThemeKind::Dark,
+            ghost_enabled: true,
+            peek_enabled: true,
+            gravity_mode: GravityMode::Normal,
+            cascade: false,
+            combo_chain: false,
+            handling: Handling::default(),
+            keybindings: KeyBindings::default(),
+            ruleset: RuleSet::guideline().name,
+            start_level: 0,
+            piece_set: PieceSet::standard(RotationSystem::Srs).name,
+            randomizer: RandomizerKind::SevenBag,
+            fixed_sequence: String::new(),
+            keep_seed: false,
+            board_width: BOARD_W,
+            board_height: BOARD_H,
+        }
+    }
+}
+
+impl Settings {
+    fn dir() -> Option<PathBuf> {
+        directories::BaseDirs::new().map(|dirs| dirs.config_dir().join("tetris-synthwave"))
+    }
+
+    fn path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join("settings.json"))
+    }
+
+    /// Built-in rule sets followed by the user's `*.json` presets from the `rulesets`
+    /// folder next to the settings file.
+    pub fn rulesets() -> Vec<RuleSet> {
+        let mut rulesets = RuleSet::builtin();
+        if let Some(dir) = Self::dir() {
+            rulesets.extend(preset::load_dir::<RuleSet>(&dir.join("rulesets")));
+        }
+        rulesets
+    }
+
+    /// The standard tetrominoes followed by the user's `*.json` and `*.ron` sets from the
+    /// `piecesets` folder next to the settings file.
+    pub fn piece_sets() -> Vec<PieceSet> {
+        let mut sets = vec![PieceSet::standard(RotationSystem::Srs)];
+        if let Some(dir) = Self::dir() {
+            sets.extend(preset::load_dir::<PieceSet>(&dir.join("piecesets")));
+        }
+        sets
+    }
+
+    /// Load settings, falling back to defaults if the file is missing or unreadable.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = Self::path().context("no config directory available")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+}
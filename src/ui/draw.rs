@@ -1,24 +1,40 @@
 use egui::{pos2, Color32, Sense, Stroke, Ui, Vec2};
 
 use super::theme::Palette;
-use crate::game::{board::Board, piece::{ActivePiece, Tetromino}};
-
-fn tet_color(t: Tetromino, pal: &Palette) -> Color32 {
-    match t {
-        Tetromino::I => Color32::from_rgb(0, 240, 240),
-        Tetromino::O => Color32::from_rgb(240, 240, 0),
-        Tetromino::T => pal.neon_magenta,
-        Tetromino::S => Color32::from_rgb(0, 240, 0),
-        Tetromino::Z => Color32::from_rgb(240, 0, 0),
-        Tetromino::J => Color32::from_rgb(0, 0, 240),
-        Tetromino::L => Color32::from_rgb(240, 160, 0),
+use crate::game::{board::{Board, CellKind, Power}, piece::{ActivePiece, Piece, PieceSet, Rot}};
+
+/// A custom piece's own color, else the theme's color for its slot.
+fn tet_color(t: Piece, pieces: &PieceSet, pal: &Palette) -> Color32 {
+    match pieces.get(t).color {
+        Some([r, g, b]) => Color32::from_rgb(r, g, b),
+        None => pal.pieces[t.index() % pal.pieces.len()],
+    }
+}
+
+fn power_color(power: Power) -> Color32 {
+    match power {
+        Power::Nova => Color32::from_rgb(255, 236, 140),
+        Power::SlowTime => Color32::from_rgb(120, 170, 255),
     }
 }
 
-/// Draw a small preview tetromino piece
-pub fn preview_piece(ui: &mut Ui, pal: &Palette, tetromino: crate::game::piece::Tetromino, size: f32) {
-    let color = tet_color(tetromino, pal);
-    let blocks = crate::game::piece::blocks(tetromino, crate::game::piece::Rot::R0);
+/// Halo and core marking a power cell, drawn over the regular mino.
+fn power_glow(painter: &egui::Painter, cell_rect: egui::Rect, power: Power) {
+    let color = power_color(power);
+    painter.rect_stroke(cell_rect.expand(1.0), 5.0, Stroke::new(4.0, color.gamma_multiply(0.25)));
+    painter.rect_stroke(cell_rect.shrink(1.0), 4.0, Stroke::new(2.0, color));
+    painter.circle_filled(cell_rect.center(), cell_rect.width() * 0.16, color);
+}
+
+/// Draw a small preview of piece `t` from `pieces` in its spawn orientation. `greyed`
+/// desaturates it (e.g. hold is unavailable).
+pub fn preview_piece(ui: &mut Ui, pal: &Palette, pieces: &PieceSet, t: Piece, size: f32, greyed: bool) {
+    let color = if greyed {
+        pal.text.gamma_multiply(0.35)
+    } else {
+        tet_color(t, pieces, pal)
+    };
+    let blocks = pieces.minos(t, Rot::R0);
     
     // Calculate bounds for centering
     let min_x = blocks.iter().map(|(x, _)| *x).min().unwrap_or(0);
@@ -33,7 +49,7 @@ pub fn preview_piece(ui: &mut Ui, pal: &Palette, tetromino: crate::game::piece::
     let rect = resp.rect;
     
     // Draw each block
-    for (bx, by) in blocks {
+    for &(bx, by) in blocks.iter() {
         let x = rect.left() + ((bx - min_x) as f32) * size;
         let y = rect.top() + ((by - min_y) as f32) * size;
         let block_rect = egui::Rect::from_min_size(egui::pos2(x, y), egui::Vec2::splat(size));
@@ -44,10 +60,47 @@ pub fn preview_piece(ui: &mut Ui, pal: &Palette, tetromino: crate::game::piece::
     }
 }
 
-/// Draw the playfield area with grid lines and game state
-pub fn playfield(ui: &mut Ui, pal: &Palette, _dims: (usize, usize), board: &Board, active: Option<&ActivePiece>, ghost: Option<&ActivePiece>, show_ghost: bool) {
+/// Neon combo chain counter with a countdown bar for the remaining window.
+pub fn combo_chain(ui: &mut Ui, pal: &Palette, multiplier: u32, remaining: f32) {
+    let active = remaining > 0.0;
+    let glow = if active { pal.neon_magenta } else { pal.text.gamma_multiply(0.4) };
+
+    let (resp, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), 44.0), Sense::hover());
+    let rect = resp.rect;
+
+    // Counter with a soft glow: a few offset, faded copies behind the main text
+    let center = pos2(rect.center().x, rect.top() + 16.0);
+    let text = format!("×{}", multiplier);
+    let font = egui::FontId::proportional(26.0);
+    if active {
+        for (dx, dy) in [(-1.5, 0.0), (1.5, 0.0), (0.0, -1.5), (0.0, 1.5)] {
+            painter.text(center + Vec2::new(dx, dy), egui::Align2::CENTER_CENTER, &text, font.clone(), glow.gamma_multiply(0.25));
+        }
+    }
+    painter.text(center, egui::Align2::CENTER_CENTER, &text, font, glow);
+
+    // Countdown bar
+    let bar = egui::Rect::from_min_size(pos2(rect.left(), rect.bottom() - 8.0), Vec2::new(rect.width(), 6.0));
+    painter.rect_filled(bar, 3.0, pal.grid.gamma_multiply(0.4));
+    if active {
+        let mut fill = bar;
+        fill.set_width(bar.width() * remaining.clamp(0.0, 1.0));
+        painter.rect_filled(fill, 3.0, pal.neon_cyan);
+        painter.rect_stroke(fill, 3.0, Stroke::new(1.0, pal.neon_cyan.gamma_multiply(0.5)));
+    }
+}
+
+/// Vanish-zone rows shown above the playfield when peeking is enabled.
+const PEEK_ROWS: usize = 2;
+
+/// Draw the playfield area with grid lines and game state.
+/// Only the visible rows are drawn; with `peek` the part of the active piece still in the
+/// vanish zone is shown faded above the board.
+#[allow(clippy::too_many_arguments)]
+pub fn playfield(ui: &mut Ui, pal: &Palette, pieces: &PieceSet, board: &Board, active: Option<&ActivePiece>, ghost: Option<&ActivePiece>, show_ghost: bool, peek: bool) {
     let cols = board.w as usize;
     let rows = board.h as usize;
+    let peek_rows = if peek { PEEK_ROWS } else { 0 };
 
     // Calculate optimal cell size to use most of the available space
     let avail = ui.available_size();
@@ -57,34 +110,39 @@ pub fn playfield(ui: &mut Ui, pal: &Palette, _dims: (usize, usize), board: &Boar
     let max_h = avail.y - margin;
     
     let cell_w = max_w / cols as f32;
-    let cell_h = max_h / rows as f32;
-    let cell_size = cell_w.min(cell_h).max(25.0); // Larger minimum for better visibility
+    let cell_h = max_h / (rows + peek_rows) as f32;
+    let cell_size = cell_w.min(cell_h).max(8.0); // Floor keeps tall boards legible on small windows
     
     let board_w = cell_size * cols as f32;
     let board_h = cell_size * rows as f32;
+    let peek_h = cell_size * peek_rows as f32;
     
     // Center the board properly using egui's centering layout
     ui.vertical_centered(|ui| {
-        ui.add_space((avail.y - board_h - margin) * 0.5);
+        ui.add_space((avail.y - board_h - peek_h - margin) * 0.5);
         
-        // Allocate the painter with the calculated size
-        let (resp, painter) = ui.allocate_painter(Vec2::new(board_w, board_h), Sense::hover());
-        let rect = resp.rect;
+        // Allocate the painter with the calculated size (peek strip sits above the board)
+        let (resp, painter) = ui.allocate_painter(Vec2::new(board_w, board_h + peek_h), Sense::hover());
+        let mut rect = resp.rect;
+        rect.min.y += peek_h;
         
-        draw_playfield_content(painter, rect, pal, board, active, ghost, show_ghost, cols, rows, cell_size);
+        draw_playfield_content(painter, rect, pal, pieces, board, active, ghost, show_ghost, cols, rows, peek_rows, cell_size);
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn draw_playfield_content(
     painter: egui::Painter,
     rect: egui::Rect,
     pal: &Palette,
+    pieces: &PieceSet,
     board: &Board,
     active: Option<&ActivePiece>,
     ghost: Option<&ActivePiece>,
     show_ghost: bool,
     cols: usize,
     rows: usize,
+    peek_rows: usize,
     cell_size: f32,
 ) {
 
@@ -133,7 +191,10 @@ fn draw_playfield_content(
                 let x0 = rect.left() + (x as f32) * cell_size;
                 let y0 = rect.top() + (y as f32) * cell_size;
                 let cell_rect = egui::Rect::from_min_size(pos2(x0, y0), Vec2::splat(cell_size));
-                let color = tet_color(cell.t, pal);
+                let color = match cell.kind {
+                    CellKind::Piece(t) => tet_color(t, pieces, pal),
+                    CellKind::Garbage => pal.garbage,
+                };
                 
                 // Filled cell with rounded corners
                 painter.rect_filled(cell_rect.shrink(2.0), 4.0, color);
@@ -141,19 +202,19 @@ fn draw_playfield_content(
                 painter.rect_stroke(cell_rect.shrink(2.0), 4.0, Stroke::new(1.0, color.gamma_multiply(1.5)));
                 // Outer border
                 painter.rect_stroke(cell_rect.shrink(1.0), 4.0, Stroke::new(0.5, pal.neon_cyan.gamma_multiply(0.8)));
+                if let Some(power) = cell.power {
+                    power_glow(&painter, cell_rect, power);
+                }
             }
         }
     }
 
     // Draw ghost piece (where the piece will land) if enabled and not same as active position
-    if show_ghost && ghost.is_some() && active.is_some() {
-        let ghost_piece = ghost.unwrap();
-        let active_piece = active.unwrap();
-        
+    if let (true, Some(ghost_piece), Some(active_piece)) = (show_ghost, ghost, active) {
         // Only draw ghost if it's at a different position than active piece
         if ghost_piece.y != active_piece.y {
-            let ghost_color = tet_color(ghost_piece.t, pal).gamma_multiply(0.4); // Semi-transparent
-            for (dx, dy) in ghost_piece.cells() {
+            let ghost_color = tet_color(ghost_piece.t, pieces, pal).gamma_multiply(0.4); // Semi-transparent
+            for &(dx, dy) in ghost_piece.cells().iter() {
                 if dx >= 0 && dy >= 0 && (dx as usize) < cols && (dy as usize) < rows {
                     let x0 = rect.left() + (dx as f32) * cell_size;
                     let y0 = rect.top() + (dy as f32) * cell_size;
@@ -168,8 +229,9 @@ fn draw_playfield_content(
  
     // Draw active falling piece with glow effect
     if let Some(piece) = active {
-        let color = tet_color(piece.t, pal);
-        for (dx, dy) in piece.cells() {
+        let color = tet_color(piece.t, pieces, pal);
+        let power = piece.power;
+        for (i, (dx, dy)) in piece.cells().iter().copied().enumerate() {
             if dx >= 0 && dy >= 0 && (dx as usize) < cols && (dy as usize) < rows {
                 let x0 = rect.left() + (dx as f32) * cell_size;
                 let y0 = rect.top() + (dy as f32) * cell_size;
@@ -179,6 +241,17 @@ fn draw_playfield_content(
                 painter.rect_filled(cell_rect.shrink(2.0), 4.0, color);
                 painter.rect_stroke(cell_rect.shrink(2.0), 4.0, Stroke::new(2.0, color.gamma_multiply(1.8)));
                 painter.rect_stroke(cell_rect.shrink(1.0), 4.0, Stroke::new(1.0, pal.neon_cyan));
+                if let Some((_, p)) = power.filter(|&(mino, _)| mino == i) {
+                    power_glow(&painter, cell_rect, p);
+                }
+            } else if dx >= 0 && dy < 0 && dy >= -(peek_rows as i16) && (dx as usize) < cols {
+                // Peek: minos still in the vanish zone, drawn faded above the board
+                let x0 = rect.left() + (dx as f32) * cell_size;
+                let y0 = rect.top() + (dy as f32) * cell_size;
+                let cell_rect = egui::Rect::from_min_size(pos2(x0, y0), Vec2::splat(cell_size));
+
+                painter.rect_filled(cell_rect.shrink(2.0), 4.0, color.gamma_multiply(0.35));
+                painter.rect_stroke(cell_rect.shrink(2.0), 4.0, Stroke::new(1.0, color.gamma_multiply(0.6)));
             }
         }
     }
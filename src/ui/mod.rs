@@ -1,3 +1,4 @@
 pub mod theme;
 pub mod panel;
 pub mod draw;
+pub mod overlay;
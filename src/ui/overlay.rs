@@ -0,0 +1,140 @@
+use egui::{Align2, Color32, Context, Frame, Margin, RichText, Stroke};
+
+use super::theme::Palette;
+use crate::game::{Callout, Game, Phase, TopOut, CALLOUT_SECS};
+
+pub struct OverlayActions {
+    pub restart: bool,
+    pub dismiss: bool,
+}
+
+impl OverlayActions {
+    pub fn none() -> Self {
+        Self {
+            restart: false,
+            dismiss: false,
+        }
+    }
+}
+
+/// Results card shown over the playfield once the game has topped out.
+/// `x_offset` shifts the card so it centers on the playfield rather than the window.
+pub fn game_over(ctx: &Context, pal: &Palette, game: &Game, x_offset: f32) -> OverlayActions {
+    let mut actions = OverlayActions::none();
+    let reason = match game.phase() {
+        Phase::GameOver(TopOut::BlockOut) => "BLOCK OUT",
+        Phase::GameOver(TopOut::LockOut) => "LOCK OUT",
+        Phase::GameOver(TopOut::Overflow) => "TOP OUT",
+        Phase::Playing => return actions,
+    };
+
+    egui::Area::new(egui::Id::new("game_over_overlay"))
+        .anchor(Align2::CENTER_CENTER, [x_offset, 0.0])
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            Frame::none()
+                .fill(pal.bg1.gamma_multiply(0.95))
+                .stroke(Stroke::new(2.0, pal.neon_magenta))
+                .rounding(10.0)
+                .inner_margin(Margin::same(20.0))
+                .show(ui, |ui| {
+                    ui.set_width(220.0);
+                    ui.vertical_centered(|ui| {
+                        ui.label(
+                            RichText::new("GAME OVER")
+                                .size(24.0)
+                                .color(pal.neon_magenta)
+                                .strong()
+                        );
+                        ui.label(
+                            RichText::new(reason)
+                                .size(11.0)
+                                .color(pal.text.gamma_multiply(0.6))
+                        );
+                    });
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    result_row(ui, pal, "Score", &format!("{:0>6}", game.score), pal.neon_magenta);
+                    result_row(ui, pal, "Lines", &format!("{}", game.lines), pal.text);
+                    result_row(ui, pal, "Level", &format!("{}", game.shown_level()), pal.neon_cyan);
+                    result_row(ui, pal, "Time", &format_time(game.elapsed()), pal.text);
+
+                    ui.add_space(12.0);
+                    ui.horizontal(|ui| {
+                        if ui.button(
+                            RichText::new("🔄 PLAY AGAIN")
+                                .size(12.0)
+                                .color(pal.neon_cyan)
+                        ).clicked() {
+                            actions.restart = true;
+                        }
+                        if ui.button(
+                            RichText::new("↩ BACK")
+                                .size(12.0)
+                                .color(pal.text.gamma_multiply(0.8))
+                        ).clicked() {
+                            actions.dismiss = true;
+                        }
+                    });
+                });
+        });
+
+    actions
+}
+
+/// Fading clear announcement ("T-SPIN DOUBLE") over the upper part of the playfield.
+pub fn callout(ctx: &Context, pal: &Palette, callout: &Callout, x_offset: f32) {
+    let fade = (1.0 - callout.age / CALLOUT_SECS).clamp(0.0, 1.0);
+    // Drift upward a little as it fades
+    let rise = callout.age * 12.0;
+
+    egui::Area::new(egui::Id::new("callout_overlay"))
+        .anchor(Align2::CENTER_TOP, [x_offset, 120.0 - rise])
+        .order(egui::Order::Foreground)
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                if callout.perfect_clear {
+                    // Pulsing gold banner that swells in, then settles
+                    let swell = 1.0 + 0.35 * (1.0 - (callout.age * 4.0).min(1.0));
+                    let pulse = (callout.age * 12.0).sin() * 0.5 + 0.5;
+                    let gold = Color32::from_rgb(255, 215, 90).lerp_to_gamma(pal.neon_magenta, pulse * 0.4);
+                    ui.label(
+                        RichText::new("PERFECT CLEAR")
+                            .size(32.0 * swell)
+                            .color(gold.gamma_multiply(fade))
+                            .strong()
+                    );
+                }
+                for (i, line) in callout.lines.iter().enumerate() {
+                    let (size, color) = if i == 0 {
+                        (26.0, pal.neon_magenta)
+                    } else {
+                        (16.0, pal.neon_cyan)
+                    };
+                    ui.label(
+                        RichText::new(line)
+                            .size(size)
+                            .color(color.gamma_multiply(fade))
+                            .strong()
+                    );
+                }
+            });
+        });
+}
+
+fn result_row(ui: &mut egui::Ui, pal: &Palette, label: &str, value: &str, value_color: Color32) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("{}:", label)).color(pal.text.gamma_multiply(0.9)));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(RichText::new(value).color(value_color).strong());
+        });
+    });
+}
+
+/// Format seconds as `m:ss.s`.
+pub fn format_time(secs: f32) -> String {
+    let minutes = (secs / 60.0) as u32;
+    format!("{}:{:04.1}", minutes, secs - minutes as f32 * 60.0)
+}
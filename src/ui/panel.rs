@@ -1,11 +1,24 @@
 use egui::{Ui, RichText, Color32, Frame, Stroke, Margin};
 use super::theme::{ThemeKind, palette};
+use crate::game::handling::{Handling, SDF_INSTANT};
+use crate::game::piece::PieceSet;
+use crate::game::random::RandomizerKind;
+use crate::game::rules::RuleSet;
+use crate::game::{MIN_BOARD_W, MAX_BOARD_W, MIN_BOARD_H, MAX_BOARD_H, MAX_START_LEVEL};
 
 pub struct PanelActions {
     pub pause_toggled: bool,
     pub restart: bool,
     pub ghost_toggled: bool,
+    pub peek_toggled: bool,
     pub gravity_pulse_toggled: bool,
+    pub cascade_toggled: bool,
+    pub combo_chain_toggled: bool,
+    pub randomizer_changed: bool,
+    pub ruleset_changed: bool,
+    pub start_level_changed: bool,
+    pub piece_set_changed: bool,
+    pub board_resized: bool,
 }
 
 impl PanelActions {
@@ -14,12 +27,21 @@ impl PanelActions {
             pause_toggled: false,
             restart: false,
             ghost_toggled: false,
+            peek_toggled: false,
             gravity_pulse_toggled: false,
+            cascade_toggled: false,
+            combo_chain_toggled: false,
+            randomizer_changed: false,
+            ruleset_changed: false,
+            start_level_changed: false,
+            piece_set_changed: false,
+            board_resized: false,
         }
     }
 }
 
-pub fn right_panel(ui: &mut Ui, theme: &mut ThemeKind, paused: bool, ghost_enabled: &mut bool, gravity_pulse_mode: &mut bool) -> PanelActions {
+#[allow(clippy::too_many_arguments)]
+pub fn right_panel(ui: &mut Ui, theme: &mut ThemeKind, paused: bool, ghost_enabled: &mut bool, peek_enabled: &mut bool, gravity_pulse_mode: &mut bool, cascade_mode: &mut bool, combo_chain_mode: &mut bool, handling: &mut Handling, rulesets: &[RuleSet], ruleset: &mut usize, start_level: &mut u32, piece_sets: &[PieceSet], piece_set: &mut usize, randomizer: &mut RandomizerKind, fixed_sequence: &mut String, board_size: &mut (i16, i16)) -> PanelActions {
     let mut actions = PanelActions::none();
     let pal = palette(*theme);
 
@@ -75,12 +97,153 @@ pub fn right_panel(ui: &mut Ui, theme: &mut ThemeKind, paused: bool, ghost_enabl
                 actions.ghost_toggled = true;
             }
 
+            if ui.checkbox(peek_enabled, 
+                RichText::new("👀 Spawn Peek")
+                    .color(pal.text)
+            ).clicked() {
+                actions.peek_toggled = true;
+            }
+
             if ui.checkbox(gravity_pulse_mode, 
                 RichText::new("🌊 Gravity: Pulse")
                     .color(pal.text)
             ).clicked() {
                 actions.gravity_pulse_toggled = true;
             }
+
+            if ui.checkbox(cascade_mode, 
+                RichText::new("🧱 Cascade Clears")
+                    .color(pal.text)
+            ).clicked() {
+                actions.cascade_toggled = true;
+            }
+
+            if ui.checkbox(combo_chain_mode, 
+                RichText::new("⚡ Combo Chain")
+                    .color(pal.text)
+            ).clicked() {
+                actions.combo_chain_toggled = true;
+            }
+        });
+
+        ui.add_space(12.0);
+
+        // Handling section: DAS/ARR in milliseconds, SDF as a gravity multiplier
+        // Rule sets such as Classic fix their own timings; the sliders then have no effect
+        let rules_handling = rulesets.get(*ruleset).is_some_and(|r| r.handling.is_some());
+        control_section(ui, &pal, "🎛 HANDLING", |ui| {
+            ui.add_enabled_ui(!rules_handling, |ui| {
+                ms_slider(ui, &pal, "DAS", &mut handling.das, 0.0..=400.0);
+                ms_slider(ui, &pal, "ARR", &mut handling.arr, 0.0..=100.0);
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("SDF").color(pal.text));
+                    ui.add(
+                        egui::Slider::new(&mut handling.sdf, 1.0..=SDF_INSTANT)
+                            .step_by(1.0)
+                            .custom_formatter(|v, _| {
+                                if v as f32 >= SDF_INSTANT { "∞".to_owned() } else { format!("{}x", v) }
+                            })
+                    );
+                });
+            });
+            if rules_handling {
+                ui.label(
+                    RichText::new("Set by the rule set")
+                        .size(10.0)
+                        .color(pal.text.gamma_multiply(0.6))
+                        .italics()
+                );
+            }
+        });
+
+        ui.add_space(12.0);
+
+        // Rule set section: picking one (or a start level) starts a new game under it
+        control_section(ui, &pal, "📜 RULESET", |ui| {
+            let selected = rulesets.get(*ruleset).map_or("", |r| r.name.as_str());
+            egui::ComboBox::from_id_salt("ruleset")
+                .selected_text(RichText::new(selected).color(pal.text))
+                .show_ui(ui, |ui| {
+                    for (i, rules) in rulesets.iter().enumerate() {
+                        if ui.selectable_value(ruleset, i, &rules.name).clicked() {
+                            actions.ruleset_changed = true;
+                        }
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Start level").color(pal.text));
+                // Numbered the way the rule set shows levels in play
+                let first = rulesets.get(*ruleset).map_or(0, |r| r.first_level);
+                let mut shown = *start_level + first;
+                let slider = ui.add(egui::Slider::new(&mut shown, first..=MAX_START_LEVEL + first));
+                if slider.changed() {
+                    *start_level = shown - first;
+                }
+                if settled(&slider) {
+                    actions.start_level_changed = true;
+                }
+            });
+        });
+
+        ui.add_space(12.0);
+
+        // Piece set section: switching sets starts a new game
+        control_section(ui, &pal, "🧩 PIECES", |ui| {
+            let selected = piece_sets.get(*piece_set).map_or("", |s| s.name.as_str());
+            egui::ComboBox::from_id_salt("piece_set")
+                .selected_text(RichText::new(selected).color(pal.text))
+                .show_ui(ui, |ui| {
+                    for (i, set) in piece_sets.iter().enumerate() {
+                        if ui.selectable_value(piece_set, i, &set.name).clicked() {
+                            actions.piece_set_changed = true;
+                        }
+                    }
+                });
+        });
+
+        ui.add_space(12.0);
+
+        // Randomizer section: switching generators starts a new game
+
+        control_section(ui, &pal, "🎲 RANDOMIZER", |ui| {
+            egui::ComboBox::from_id_salt("randomizer")
+                .selected_text(RichText::new(randomizer.label()).color(pal.text))
+                .show_ui(ui, |ui| {
+                    for kind in RandomizerKind::ALL {
+                        if ui.selectable_value(randomizer, kind, kind.label()).clicked() {
+                            actions.randomizer_changed = true;
+                        }
+                    }
+                });
+            if *randomizer == RandomizerKind::Fixed {
+                let edit = ui.add(
+                    egui::TextEdit::singleline(fixed_sequence)
+                        .hint_text("e.g. TSZIOJL, Enter")
+                        .desired_width(140.0)
+                );
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    actions.randomizer_changed = true;
+                }
+            }
+        });
+
+        ui.add_space(12.0);
+
+        // Board size section: resizing starts a new game
+        control_section(ui, &pal, "📐 BOARD", |ui| {
+            let (w, h) = board_size;
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Width").color(pal.text));
+                if settled(&ui.add(egui::Slider::new(w, MIN_BOARD_W..=MAX_BOARD_W))) {
+                    actions.board_resized = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Height").color(pal.text));
+                if settled(&ui.add(egui::Slider::new(h, MIN_BOARD_H..=MAX_BOARD_H))) {
+                    actions.board_resized = true;
+                }
+            });
         });
 
         ui.add_space(12.0);
@@ -104,15 +267,29 @@ pub fn right_panel(ui: &mut Ui, theme: &mut ThemeKind, paused: bool, ghost_enabl
                 ).clicked() {
                     *theme = ThemeKind::Light;
                 }
+
+                let nes_selected = matches!(theme, ThemeKind::Nes);
+                if ui.selectable_label(nes_selected,
+                    RichText::new("👾 NES")
+                        .color(if nes_selected { pal.neon_magenta } else { pal.text.gamma_multiply(0.7) })
+                ).clicked() {
+                    *theme = ThemeKind::Nes;
+                }
             });
         });
 
         ui.add_space(12.0);
 
-        // Preview section
+        // Preview section: the queue length comes from the rule set
+        let preview = rulesets.get(*ruleset).map_or(0, |r| r.preview);
         control_section(ui, &pal, "🔮 PREVIEW", |ui| {
+            let queue = match preview {
+                0 => "Next: off".to_owned(),
+                1 => "Next: 1 piece".to_owned(),
+                n => format!("Next: {}-piece queue", n),
+            };
             ui.label(
-                RichText::new("Next: [3-piece queue]")
+                RichText::new(queue)
                     .color(pal.text.gamma_multiply(0.8))
                     .italics()
             );
@@ -122,6 +299,23 @@ pub fn right_panel(ui: &mut Ui, theme: &mut ThemeKind, paused: bool, ghost_enabl
     actions
 }
 
+/// A slider edit is final once its drag ends, or at once for clicks and typed values,
+/// so settings that restart the game don't do so on every frame of a drag.
+fn settled(slider: &egui::Response) -> bool {
+    slider.drag_stopped() || (slider.changed() && !slider.dragged())
+}
+
+/// Slider editing a duration stored in seconds, shown in milliseconds.
+fn ms_slider(ui: &mut Ui, pal: &crate::ui::theme::Palette, label: &str, secs: &mut f32, range: std::ops::RangeInclusive<f32>) {
+    let mut ms = (*secs * 1000.0).round();
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(label).color(pal.text));
+        if ui.add(egui::Slider::new(&mut ms, range).step_by(1.0).suffix(" ms")).changed() {
+            *secs = ms / 1000.0;
+        }
+    });
+}
+
 fn control_section<R>(
     ui: &mut Ui,
     pal: &crate::ui::theme::Palette,
@@ -1,9 +1,13 @@
 use egui::{Color32, Context, Visuals};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ThemeKind {
     Dark,
     Light,
+    /// NES-style palette: black well and the level 0 piece colours.
+    Nes,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -14,6 +18,23 @@ pub struct Palette {
     pub grid: Color32,
     pub neon_cyan: Color32,
     pub neon_magenta: Color32,
+    /// Piece colours, indexed by `Piece::index`.
+    pub pieces: [Color32; 7],
+    /// Garbage rows pushed up from below.
+    pub garbage: Color32,
+}
+
+/// Guideline piece colours with `t` for the T piece.
+fn guideline_pieces(t: Color32) -> [Color32; 7] {
+    [
+        Color32::from_rgb(0, 240, 240),
+        Color32::from_rgb(240, 240, 0),
+        t,
+        Color32::from_rgb(0, 240, 0),
+        Color32::from_rgb(240, 0, 0),
+        Color32::from_rgb(0, 0, 240),
+        Color32::from_rgb(240, 160, 0),
+    ]
 }
 
 pub fn palette(theme: ThemeKind) -> Palette {
@@ -29,6 +50,8 @@ pub fn palette(theme: ThemeKind) -> Palette {
             // Neon accents
             neon_cyan: Color32::from_rgb(0, 248, 255),
             neon_magenta: Color32::from_rgb(255, 0, 180),
+            pieces: guideline_pieces(Color32::from_rgb(255, 0, 180)),
+            garbage: Color32::from_rgb(96, 100, 120),
         },
         ThemeKind::Light => Palette {
             // Softer gradient background - less bright
@@ -40,7 +63,25 @@ pub fn palette(theme: ThemeKind) -> Palette {
             // More contrasted accents
             neon_cyan: Color32::from_rgb(20, 120, 160),
             neon_magenta: Color32::from_rgb(160, 20, 110),
+            pieces: guideline_pieces(Color32::from_rgb(160, 20, 110)),
+            garbage: Color32::from_rgb(140, 136, 150),
         },
+        ThemeKind::Nes => {
+            let white = Color32::from_rgb(252, 252, 252);
+            let blue = Color32::from_rgb(0, 88, 248);
+            let sky = Color32::from_rgb(60, 188, 252);
+            Palette {
+                bg0: Color32::BLACK,
+                bg1: Color32::from_rgb(24, 24, 24),
+                text: white,
+                grid: Color32::from_rgba_premultiplied(60, 60, 60, 120),
+                neon_cyan: sky,
+                neon_magenta: Color32::from_rgb(228, 0, 88),
+                // I, O and T are white; J and S take one level colour, Z and L the other
+                pieces: [white, white, white, blue, sky, blue, sky],
+                garbage: Color32::from_rgb(124, 124, 124),
+            }
+        }
     }
 }
 
@@ -49,7 +90,7 @@ pub fn palette(theme: ThemeKind) -> Palette {
 pub fn apply(theme: ThemeKind, ctx: &Context) {
     let pal = palette(theme);
     let mut visuals = match theme {
-        ThemeKind::Dark => Visuals::dark(),
+        ThemeKind::Dark | ThemeKind::Nes => Visuals::dark(),
         ThemeKind::Light => Visuals::light(),
     };
 